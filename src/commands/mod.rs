@@ -3,4 +3,5 @@ pub mod help;
 pub mod moderation;
 pub mod nsfw;
 pub mod system;
+pub mod utility;
 pub mod web;
@@ -0,0 +1,697 @@
+use super::cases::{create_case, CaseAction};
+use crate::core::{
+    constants::{ERROR_COLOR, MAIN_COLOR},
+    guild_config::{update_guild_config, GuildConfigs},
+    moderation::notify_user,
+    storage::get_store,
+    util::{format_duration, is_guild_channel, parse_duration},
+};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        guild::Member,
+        id::{ChannelId, GuildId},
+    },
+    prelude::Context,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+// Verification level that requires a verified phone number
+const LOCKDOWN_VERIFICATION_LEVEL: u64 = 4;
+// Guild feature that pauses all invites of a guild
+const INVITES_DISABLED_FEATURE: &str = "INVITES_DISABLED";
+const LOCKDOWN_KICK_REASON: &str = "The server is in lockdown";
+
+lazy_static! {
+    // Recent counted joins per guild, oldest first
+    static ref RECENT_JOINS: Mutex<HashMap<u64, VecDeque<DateTime<Utc>>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct AntiraidData {
+    guilds: HashMap<u64, AntiraidConfig>,
+}
+
+impl GuildConfigs for AntiraidData {
+    type Config = AntiraidConfig;
+
+    fn configs_mut(&mut self) -> &mut HashMap<u64, AntiraidConfig> {
+        &mut self.guilds
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntiraidConfig {
+    enabled: bool,
+    join_count: usize,
+    join_seconds: i64,
+    min_account_age_seconds: Option<i64>,
+    default_avatars: bool,
+    actions: Vec<LockdownAction>,
+    alert_channel_id: Option<u64>,
+    lockdown: Option<Lockdown>,
+}
+
+impl Default for AntiraidConfig {
+    fn default() -> Self {
+        AntiraidConfig {
+            enabled: false,
+            join_count: 10,
+            join_seconds: 10,
+            min_account_age_seconds: None,
+            default_avatars: false,
+            actions: vec![LockdownAction::Verification],
+            alert_channel_id: None,
+            lockdown: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum LockdownAction {
+    Verification,
+    Invites,
+    Kick,
+}
+
+impl LockdownAction {
+    fn parse(name: &str) -> Option<LockdownAction> {
+        match name.to_lowercase().as_str() {
+            "verification" => Some(LockdownAction::Verification),
+            "invites" => Some(LockdownAction::Invites),
+            "kick" => Some(LockdownAction::Kick),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &str {
+        match self {
+            LockdownAction::Verification => "raise the verification level",
+            LockdownAction::Invites => "pause invites",
+            LockdownAction::Kick => "kick new members",
+        }
+    }
+}
+
+/// The guild settings that have been changed by a lockdown, so they can be reverted afterwards
+#[derive(Serialize, Deserialize, Clone)]
+struct Lockdown {
+    since: DateTime<Utc>,
+    previous_verification_level: Option<u64>,
+    paused_invites: bool,
+    kick_newcomers: bool,
+}
+
+#[command]
+#[sub_commands(
+    antiraid_enable,
+    antiraid_disable,
+    antiraid_threshold,
+    antiraid_fresh,
+    antiraid_avatars,
+    antiraid_actions,
+    antiraid_alerts
+)]
+#[description(
+    "Shows the anti-raid settings of this server. \
+    When too many members join in a short time, the server is put into lockdown \
+    and moderators are alerted. Use `lockdown off` to end a lockdown."
+)]
+pub async fn antiraid(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let fields = {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let store = store.read().await;
+        let default_config = AntiraidConfig::default();
+        let config = store.guilds.get(&guild_id.0).unwrap_or(&default_config);
+
+        let mut counted_joins = vec![];
+        if let Some(seconds) = config.min_account_age_seconds {
+            counted_joins.push(format!(
+                "accounts younger than {}",
+                format_duration(Duration::seconds(seconds))
+            ));
+        }
+        if config.default_avatars {
+            counted_joins.push("accounts without avatar".to_string());
+        }
+        if counted_joins.is_empty() {
+            counted_joins.push("all joins".to_string());
+        }
+
+        vec![
+            (
+                "Status",
+                match config.enabled {
+                    true => "on".to_string(),
+                    false => "off".to_string(),
+                },
+                true,
+            ),
+            (
+                "Threshold",
+                format!(
+                    "{} joins in {}",
+                    config.join_count,
+                    format_duration(Duration::seconds(config.join_seconds))
+                ),
+                true,
+            ),
+            (
+                "Alerts",
+                match config.alert_channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => "none".to_string(),
+                },
+                true,
+            ),
+            ("Counted joins", counted_joins.join(", "), false),
+            (
+                "Lockdown actions",
+                config
+                    .actions
+                    .iter()
+                    .map(|a| a.describe())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+                false,
+            ),
+            (
+                "Lockdown",
+                match &config.lockdown {
+                    Some(lockdown) => format!(
+                        "active since {}",
+                        lockdown.since.format("%b %e %Y, %H:%M UTC")
+                    ),
+                    None => "inactive".to_string(),
+                },
+                false,
+            ),
+        ]
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Anti-raid settings")
+                    .fields(fields)
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("enable")]
+#[aliases("on")]
+#[description("Enables raid detection on this server")]
+async fn antiraid_enable(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.enabled = true;
+        Ok("Enabled raid detection".to_string())
+    })
+    .await
+}
+
+#[command("disable")]
+#[aliases("off")]
+#[description("Disables raid detection on this server. An active lockdown stays active.")]
+async fn antiraid_disable(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.enabled = false;
+        Ok("Disabled raid detection".to_string())
+    })
+    .await
+}
+
+#[command("threshold")]
+#[description("Sets how many joins within the given time trigger a lockdown")]
+#[usage("<joins> <time>")]
+#[example("10 30s")]
+#[min_args(2)]
+async fn antiraid_threshold(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let join_count = args
+        .single::<usize>()
+        .ok()
+        .filter(|count| *count >= 2)
+        .ok_or_else(|| CommandError::from("The amount of joins must be at least 2"))?;
+    let duration = args
+        .single::<String>()
+        .ok()
+        .and_then(|d| parse_duration(&d))
+        .filter(|d| *d <= Duration::hours(1))
+        .ok_or_else(|| CommandError::from("Please supply a valid time of up to 1 hour"))?;
+
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.join_count = join_count;
+        config.join_seconds = duration.num_seconds();
+        Ok(format!(
+            "A lockdown is now triggered by **{} joins** in **{}**",
+            join_count,
+            format_duration(duration)
+        ))
+    })
+    .await
+}
+
+#[command("fresh")]
+#[description(
+    "Only counts joins of accounts younger than the given age. \
+    Can be combined with `avatars`, in which case either criteria counts."
+)]
+#[usage("<account age|off>")]
+#[example("7d")]
+#[min_args(1)]
+async fn antiraid_fresh(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let arg = args.single::<String>()?;
+    let age = match arg.eq_ignore_ascii_case("off") {
+        true => None,
+        false => Some(
+            parse_duration(&arg)
+                .ok_or_else(|| CommandError::from("Please supply a valid account age or `off`"))?,
+        ),
+    };
+
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.min_account_age_seconds = age.map(|a| a.num_seconds());
+        Ok(match age {
+            Some(age) => format!(
+                "Only joins of accounts younger than **{}** are counted",
+                format_duration(age)
+            ),
+            None => "Joins are counted regardless of account age".to_string(),
+        })
+    })
+    .await
+}
+
+#[command("avatars")]
+#[description(
+    "Only counts joins of accounts that still use a default avatar. \
+    Can be combined with `fresh`, in which case either criteria counts."
+)]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn antiraid_avatars(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let default_avatars = match args.single::<String>()?.to_lowercase().as_str() {
+        "on" => true,
+        "off" => false,
+        _ => return Err(CommandError::from("Please supply either `on` or `off`")),
+    };
+
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.default_avatars = default_avatars;
+        Ok(match default_avatars {
+            true => "Only joins of accounts with a default avatar are counted".to_string(),
+            false => "Joins are counted regardless of avatar".to_string(),
+        })
+    })
+    .await
+}
+
+#[command("actions")]
+#[description(
+    "Sets what happens during a lockdown: \n\
+    - `verification`: raises the verification level to the highest one \n\
+    - `invites`: pauses all invites \n\
+    - `kick`: kicks everyone that joins during the lockdown"
+)]
+#[usage("<action> [action...]")]
+#[example("verification invites")]
+#[min_args(1)]
+async fn antiraid_actions(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let mut actions = vec![];
+
+    for arg in args.raw() {
+        let action = LockdownAction::parse(arg).ok_or_else(|| {
+            CommandError::from(format!(
+                "Unknown action `{}`. Use `verification`, `invites` or `kick`",
+                arg
+            ))
+        })?;
+
+        if !actions.contains(&action) {
+            actions.push(action);
+        }
+    }
+
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        let description = format!(
+            "A lockdown will now **{}**",
+            actions
+                .iter()
+                .map(|a| a.describe())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+        config.actions = actions;
+        Ok(description)
+    })
+    .await
+}
+
+#[command("alerts")]
+#[description("Sets the channel that moderators are alerted in when a raid is detected")]
+#[usage("<#channel|off>")]
+#[example("#mod-chat")]
+#[min_args(1)]
+async fn antiraid_alerts(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel_id = match args.current() {
+        Some("off") => None,
+        _ => Some(
+            args.single::<ChannelId>()
+                .map_err(|_| CommandError::from("Please supply a valid channel mention or id"))?,
+        ),
+    };
+
+    if let Some(channel_id) = channel_id {
+        if !is_guild_channel(ctx, msg.guild_id.unwrap(), channel_id).await {
+            return Err(CommandError::from("Please supply a channel of this server"));
+        }
+    }
+
+    update_guild_config::<AntiraidData, _>(ctx, msg, |config| {
+        config.alert_channel_id = channel_id.map(|c| c.0);
+        Ok(match channel_id {
+            Some(channel_id) => format!("Raid alerts are posted to <#{}>", channel_id.0),
+            None => "Raid alerts are turned off".to_string(),
+        })
+    })
+    .await
+}
+
+#[command]
+#[sub_commands(lockdown_off)]
+#[description(
+    "Manually puts the server into lockdown, applying the actions set with `antiraid actions`. \
+    Use `lockdown off` to revert all changes of a lockdown."
+)]
+pub async fn lockdown(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let description = match start_lockdown(ctx, guild_id).await? {
+        true => "The server is now in lockdown. Use `lockdown off` to end it.",
+        false => "The server is already in lockdown",
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(ERROR_COLOR).description(description))
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("off")]
+#[aliases("end")]
+#[description("Ends the lockdown and reverts the verification level and invites")]
+async fn lockdown_off(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    // The lockdown is only forgotten once it's reverted, so a failed revert can be retried
+    let lockdown = {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .guilds
+            .get(&guild_id.0)
+            .and_then(|config| config.lockdown.clone())
+            .ok_or_else(|| CommandError::from("The server isn't in lockdown"))?
+    };
+
+    let mut map = Map::new();
+
+    if let Some(level) = lockdown.previous_verification_level {
+        map.insert("verification_level".to_string(), json!(level));
+    }
+
+    if lockdown.paused_invites {
+        let (_, features) = get_guild_settings(ctx, guild_id).await?;
+        let features: Vec<String> = features
+            .into_iter()
+            .filter(|f| f != INVITES_DISABLED_FEATURE)
+            .collect();
+        map.insert("features".to_string(), json!(features));
+    }
+
+    if !map.is_empty() {
+        ctx.http.edit_guild(guild_id.0, &map).await?;
+    }
+
+    {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let mut store = store.write().await;
+
+        if let Some(config) = store.guilds.get_mut(&guild_id.0) {
+            config.lockdown = None;
+            store.save();
+        }
+    }
+
+    info!("Lockdown ended in guild {}", guild_id);
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description("The lockdown has ended and all changes have been reverted")
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Tracks joins to detect raids, and enforces an active lockdown on new members
+pub async fn check_member_join(ctx: &Context, guild_id: GuildId, member: &Member) {
+    let (kick_newcomer, raid_detected) = {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let store = store.read().await;
+
+        let config = match store.guilds.get(&guild_id.0) {
+            Some(config) => config,
+            None => return,
+        };
+
+        match &config.lockdown {
+            Some(lockdown) => (lockdown.kick_newcomers, false),
+            None => (
+                false,
+                config.enabled && register_join(config, guild_id, member),
+            ),
+        }
+    };
+
+    if kick_newcomer {
+        notify_user(
+            ctx,
+            guild_id,
+            member.user.id,
+            "kicked",
+            "The server is in lockdown, please try again later",
+        )
+        .await;
+
+        match member
+            .kick_with_reason(&ctx.http, LOCKDOWN_KICK_REASON)
+            .await
+        {
+            Ok(()) => {
+                let bot_id = ctx.cache.current_user_id().await;
+                create_case(
+                    ctx,
+                    guild_id,
+                    CaseAction::Kick,
+                    member.user.id,
+                    bot_id,
+                    LOCKDOWN_KICK_REASON,
+                    None,
+                )
+                .await;
+            }
+            Err(why) => error!(
+                "Couldn't kick {} during lockdown: {:?}",
+                member.user.id, why
+            ),
+        }
+        return;
+    }
+
+    if !raid_detected {
+        return;
+    }
+
+    info!("Raid detected in guild {}", guild_id);
+
+    let lockdown_result = start_lockdown(ctx, guild_id).await;
+    if let Err(why) = &lockdown_result {
+        error!("Couldn't start lockdown in guild {}: {:?}", guild_id, why);
+    }
+
+    let (alert_channel_id, join_count, join_seconds) = {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let store = store.read().await;
+        let config = &store.guilds[&guild_id.0];
+
+        (
+            config.alert_channel_id,
+            config.join_count,
+            config.join_seconds,
+        )
+    };
+
+    let joins = format!(
+        "**{}** members joined within **{}**",
+        join_count,
+        format_duration(Duration::seconds(join_seconds))
+    );
+    let description = match lockdown_result {
+        Ok(_) => format!(
+            "{}, so the server has been put into lockdown. \
+            Use `lockdown off` once the raid is over.",
+            joins
+        ),
+        Err(why) => format!(
+            "{}, but the lockdown failed: {}\n\
+            Please check my permissions and take action manually.",
+            joins, why
+        ),
+    };
+
+    if let Some(channel_id) = alert_channel_id {
+        let _ = ChannelId(channel_id)
+            .send_message(&ctx.http, |m| {
+                m.content("@here").embed(|e| {
+                    e.colour(ERROR_COLOR)
+                        .title("Raid detected")
+                        .description(description)
+                })
+            })
+            .await;
+    }
+}
+
+/// Records a join if it counts towards a raid. Returns whether the raid threshold has been reached.
+fn register_join(config: &AntiraidConfig, guild_id: GuildId, member: &Member) -> bool {
+    let now = Utc::now();
+
+    let is_fresh = config.min_account_age_seconds.is_some_and(|seconds| {
+        now.signed_duration_since(member.user.id.created_at()) < Duration::seconds(seconds)
+    });
+    let has_default_avatar = config.default_avatars && member.user.avatar.is_none();
+    let has_filters = config.min_account_age_seconds.is_some() || config.default_avatars;
+
+    if has_filters && !is_fresh && !has_default_avatar {
+        return false;
+    }
+
+    let mut recent_joins = RECENT_JOINS.lock().unwrap();
+    let joins = recent_joins.entry(guild_id.0).or_default();
+
+    joins.push_back(now);
+    while joins.front().is_some_and(|join| {
+        now.signed_duration_since(*join) > Duration::seconds(config.join_seconds)
+    }) {
+        joins.pop_front();
+    }
+
+    if joins.len() >= config.join_count {
+        joins.clear();
+        return true;
+    }
+
+    false
+}
+
+/// Applies the configured lockdown actions. Returns false if the guild already is in lockdown.
+async fn start_lockdown(ctx: &Context, guild_id: GuildId) -> Result<bool, CommandError> {
+    let actions = {
+        let store = get_store::<AntiraidData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0) {
+            Some(config) if config.lockdown.is_some() => return Ok(false),
+            Some(config) => config.actions.clone(),
+            None => AntiraidConfig::default().actions,
+        }
+    };
+
+    let (verification_level, features) = get_guild_settings(ctx, guild_id).await?;
+
+    let mut lockdown = Lockdown {
+        since: Utc::now(),
+        previous_verification_level: None,
+        paused_invites: false,
+        kick_newcomers: actions.contains(&LockdownAction::Kick),
+    };
+    let mut map = Map::new();
+
+    if actions.contains(&LockdownAction::Verification)
+        && verification_level < LOCKDOWN_VERIFICATION_LEVEL
+    {
+        map.insert(
+            "verification_level".to_string(),
+            json!(LOCKDOWN_VERIFICATION_LEVEL),
+        );
+        lockdown.previous_verification_level = Some(verification_level);
+    }
+
+    if actions.contains(&LockdownAction::Invites)
+        && !features.iter().any(|f| f == INVITES_DISABLED_FEATURE)
+    {
+        let mut features = features;
+        features.push(INVITES_DISABLED_FEATURE.to_string());
+        map.insert("features".to_string(), json!(features));
+        lockdown.paused_invites = true;
+    }
+
+    if !map.is_empty() {
+        ctx.http.edit_guild(guild_id.0, &map).await?;
+    }
+
+    let store = get_store::<AntiraidData>(ctx).await;
+    let mut store = store.write().await;
+    store.guilds.entry(guild_id.0).or_default().lockdown = Some(lockdown);
+    store.save();
+
+    info!("Lockdown started in guild {}", guild_id);
+    Ok(true)
+}
+
+/// Returns the verification level and the features of a guild
+async fn get_guild_settings(
+    ctx: &Context,
+    guild_id: GuildId,
+) -> Result<(u64, Vec<String>), CommandError> {
+    match ctx
+        .cache
+        .guild_field(guild_id, |g| {
+            (g.verification_level.num(), g.features.clone())
+        })
+        .await
+    {
+        Some(settings) => Ok(settings),
+        None => {
+            let guild = guild_id.to_partial_guild(&ctx.http).await?;
+            Ok((guild.verification_level.num(), guild.features))
+        }
+    }
+}
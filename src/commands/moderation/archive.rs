@@ -0,0 +1,363 @@
+use crate::core::constants::MAIN_COLOR;
+use chrono::Utc;
+use log::debug;
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    http::AttachmentType,
+    model::{
+        channel::{Embed, Message, ReactionType},
+        id::{ChannelId, MessageId},
+        Permissions,
+    },
+    prelude::Context,
+};
+use std::borrow::Cow;
+
+const REQUESTS_PER_ITER: u64 = 100;
+const MAX_ARCHIVED_MESSAGES: usize = 10000;
+
+#[command]
+#[aliases("export", "transcript")]
+#[description(
+    "Exports a channel into an html transcript and a json dump, \
+    including embeds, attachments, reactions and replies. \
+    By default the whole channel (up to 10000 messages) is exported, \
+    but you can also define a clear start and/or end point."
+)]
+#[usage("[#channel or channel id] [starting message] [ending message]")]
+#[example("")]
+#[example("#tickets")]
+#[example("#events 725681148134424596 725681148134424582")]
+pub async fn archive(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    // Get the optional channel to archive, defaulting to the current one.
+    // Channels can be given as mention or id, any other number is the starting message.
+    let guild_channels = msg.guild_id.unwrap().channels(&ctx.http).await?;
+    let channel_arg = match args.current() {
+        Some(arg) if arg.starts_with("<#") => Some(
+            args.single::<ChannelId>()
+                .map_err(|_| CommandError::from("Please supply a valid channel mention"))?,
+        ),
+        Some(arg) => arg
+            .parse::<u64>()
+            .ok()
+            .map(ChannelId)
+            .filter(|id| guild_channels.contains_key(id))
+            .inspect(|_| {
+                args.advance();
+            }),
+        None => None,
+    };
+
+    let channel_id = match channel_arg {
+        Some(channel_id) => {
+            let channel = guild_channels
+                .get(&channel_id)
+                .ok_or_else(|| CommandError::from("That channel isn't part of this server"))?;
+
+            // Moderators can only export what they could read themselves
+            let permissions = channel
+                .permissions_for_user(&ctx.cache, msg.author.id)
+                .await?;
+            if !permissions.contains(Permissions::READ_MESSAGES | Permissions::READ_MESSAGE_HISTORY)
+            {
+                return Err(CommandError::from(
+                    "You need to be able to view and read the history of that channel",
+                ));
+            }
+
+            channel_id
+        }
+        None => msg.channel_id,
+    };
+
+    // Get optional start and end parameters ("to" message older than "from" message), like in fetch.
+    // The starting message itself is included in the archive.
+    let from_msg_id_arg = args.single::<u64>().map(|id| id + 1).unwrap_or(msg.id.0);
+    let to_msg_id_arg = args.single::<u64>().unwrap_or_default();
+
+    let mut last_message_id = MessageId(from_msg_id_arg);
+    let mut archived_messages: Vec<Message> = vec![];
+    let mut end_reached = false;
+
+    while !end_reached {
+        // Show typing status
+        let _ = msg.channel_id.broadcast_typing(&ctx.http).await;
+
+        let messages: Vec<Message> = channel_id
+            .messages(&ctx.http, |retriever| {
+                retriever.before(last_message_id).limit(REQUESTS_PER_ITER)
+            })
+            .await?;
+
+        debug!("Requested {} new messages from discord", &REQUESTS_PER_ITER);
+
+        if messages.len() < REQUESTS_PER_ITER as usize {
+            end_reached = true;
+        }
+
+        for message in messages {
+            if message.id.0 < to_msg_id_arg || archived_messages.len() >= MAX_ARCHIVED_MESSAGES {
+                end_reached = true;
+                break;
+            }
+
+            last_message_id = message.id;
+            archived_messages.push(message);
+        }
+    }
+
+    // Messages are retrieved newest first, but transcripts read from top to bottom
+    archived_messages.reverse();
+
+    let channel_name = channel_id
+        .name(&ctx.cache)
+        .await
+        .unwrap_or_else(|| channel_id.0.to_string());
+    let file_name = format!("{}-{}", channel_name, Utc::now().format("%Y-%m-%d"));
+
+    let html = render_html_transcript(&channel_name, &archived_messages);
+    let json = serde_json::to_vec_pretty(&archived_messages)?;
+
+    let html_attachment = AttachmentType::Bytes {
+        data: Cow::from(html.into_bytes()),
+        filename: format!("{}.html", file_name),
+    };
+    let json_attachment = AttachmentType::Bytes {
+        data: Cow::from(json),
+        filename: format!("{}.json", file_name),
+    };
+
+    // The summary is only posted once the files made it, i.e. they might exceed the upload limit
+    msg.channel_id
+        .send_files(&ctx.http, vec![html_attachment, json_attachment], |m| m)
+        .await
+        .map_err(|_| {
+            CommandError::from("The archive couldn't be uploaded, it might be too large")
+        })?;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Channel archive")
+                    .description(format!(
+                        "Archived **{}** messages of <#{}>.\n\
+                        The html file can be opened in any browser, \
+                        the json file contains the raw message data.",
+                        archived_messages.len(),
+                        channel_id.0
+                    ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Renders the messages into a self-contained html page that resembles the discord client
+fn render_html_transcript(channel_name: &str, messages: &[Message]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n\
+        <html>\n\
+        <head>\n\
+        <meta charset=\"utf-8\">\n\
+        <title>#{channel}</title>\n\
+        <style>{style}</style>\n\
+        </head>\n\
+        <body>\n\
+        <h1>#{channel}</h1>\n\
+        <p class=\"info\">{count} messages, exported on {date}</p>\n",
+        channel = escape_html(channel_name),
+        style = TRANSCRIPT_STYLE,
+        count = messages.len(),
+        date = Utc::now().format("%b %e %Y, %H:%M UTC"),
+    );
+
+    for message in messages {
+        html.push_str(&format!(
+            "<div class=\"message\" id=\"{}\">\n",
+            message.id.0
+        ));
+
+        if let Some(referenced) = &message.referenced_message {
+            html.push_str(&format!(
+                "<a class=\"reply\" href=\"#{}\">↪ <b>{}</b> {}</a>\n",
+                referenced.id.0,
+                escape_html(&referenced.author.name),
+                escape_html(&truncate(&referenced.content, 100))
+            ));
+        }
+
+        html.push_str(&format!(
+            "<img class=\"avatar\" src=\"{}\">\n\
+            <div class=\"body\">\n\
+            <span class=\"author\">{}</span> <span class=\"timestamp\">{}{}</span>\n",
+            escape_url(&message.author.face()),
+            escape_html(&message.author.tag()),
+            message.timestamp.format("%Y-%m-%d %H:%M"),
+            match message.edited_timestamp {
+                Some(_) => " (edited)",
+                None => "",
+            }
+        ));
+
+        if !message.content.is_empty() {
+            html.push_str(&format!(
+                "<div class=\"content\">{}</div>\n",
+                escape_html(&message.content).replace('\n', "<br>")
+            ));
+        }
+
+        for attachment in &message.attachments {
+            match attachment.width {
+                Some(_) => html.push_str(&format!(
+                    "<a href=\"{url}\"><img class=\"attachment\" src=\"{url}\"></a>\n",
+                    url = escape_url(&attachment.url)
+                )),
+                None => html.push_str(&format!(
+                    "<a class=\"file\" href=\"{}\">📎 {} ({} KB)</a>\n",
+                    escape_url(&attachment.url),
+                    escape_html(&attachment.filename),
+                    attachment.size / 1024
+                )),
+            }
+        }
+
+        for embed in &message.embeds {
+            html.push_str(&render_html_embed(embed));
+        }
+
+        if !message.reactions.is_empty() {
+            html.push_str("<div class=\"reactions\">");
+            for reaction in &message.reactions {
+                let emoji = match &reaction.reaction_type {
+                    ReactionType::Custom { id, name, .. } => format!(
+                        "<img class=\"emoji\" src=\"https://cdn.discordapp.com/emojis/{}.png\" title=\"{}\">",
+                        id.0,
+                        escape_html(name.as_deref().unwrap_or_default())
+                    ),
+                    reaction_type => escape_html(&reaction_type.to_string()),
+                };
+
+                html.push_str(&format!(
+                    "<span class=\"reaction\">{} {}</span>",
+                    emoji, reaction.count
+                ));
+            }
+            html.push_str("</div>\n");
+        }
+
+        html.push_str("</div>\n</div>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_html_embed(embed: &Embed) -> String {
+    let mut html = format!(
+        "<div class=\"embed\" style=\"border-color: #{:06x}\">\n",
+        embed.colour.0
+    );
+
+    if let Some(author) = &embed.author {
+        html.push_str(&format!(
+            "<div class=\"embed-author\">{}</div>\n",
+            escape_html(&author.name)
+        ));
+    }
+
+    if let Some(title) = &embed.title {
+        match &embed.url {
+            Some(url) => html.push_str(&format!(
+                "<a class=\"embed-title\" href=\"{}\">{}</a>\n",
+                escape_url(url),
+                escape_html(title)
+            )),
+            None => html.push_str(&format!(
+                "<div class=\"embed-title\">{}</div>\n",
+                escape_html(title)
+            )),
+        }
+    }
+
+    if let Some(description) = &embed.description {
+        html.push_str(&format!(
+            "<div class=\"content\">{}</div>\n",
+            escape_html(description).replace('\n', "<br>")
+        ));
+    }
+
+    for field in &embed.fields {
+        html.push_str(&format!(
+            "<div class=\"embed-field\"><b>{}</b><br>{}</div>\n",
+            escape_html(&field.name),
+            escape_html(&field.value).replace('\n', "<br>")
+        ));
+    }
+
+    if let Some(image) = &embed.image {
+        html.push_str(&format!(
+            "<img class=\"attachment\" src=\"{}\">\n",
+            escape_url(&image.url)
+        ));
+    }
+
+    if let Some(footer) = &embed.footer {
+        html.push_str(&format!(
+            "<div class=\"embed-footer\">{}</div>\n",
+            escape_html(&footer.text)
+        ));
+    }
+
+    html.push_str("</div>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escapes a url for use in an attribute. Only web links are kept,
+/// so embeds can't sneak `javascript:` or other schemes into the transcript.
+fn escape_url(url: &str) -> String {
+    let lowercase = url.to_ascii_lowercase();
+    match lowercase.starts_with("https://") || lowercase.starts_with("http://") {
+        true => escape_html(url),
+        false => "#".to_string(),
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+const TRANSCRIPT_STYLE: &str = "\
+body { background: #36393f; color: #dcddde; font-family: sans-serif; margin: 20px; }\
+h1 { color: #fff; margin-bottom: 0; }\
+a { color: #00aff4; }\
+.info { color: #72767d; }\
+.message { display: flex; flex-wrap: wrap; padding: 6px 0; }\
+.reply { width: 100%; margin-left: 56px; font-size: 0.85em; color: #b9bbbe; text-decoration: none; }\
+.avatar { width: 40px; height: 40px; border-radius: 50%; margin-right: 16px; }\
+.body { flex: 1; min-width: 0; }\
+.author { color: #fff; font-weight: bold; }\
+.timestamp { color: #72767d; font-size: 0.75em; }\
+.content { word-wrap: break-word; }\
+.attachment { display: block; max-width: 400px; max-height: 300px; margin-top: 4px; border-radius: 4px; }\
+.file { display: block; margin-top: 4px; }\
+.embed { background: #2f3136; border-left: 4px solid; border-radius: 4px; padding: 8px 12px; margin-top: 4px; max-width: 520px; }\
+.embed-author, .embed-footer { font-size: 0.8em; color: #b9bbbe; }\
+.embed-title { display: block; color: #fff; font-weight: bold; margin-bottom: 4px; }\
+.embed-field { margin-top: 6px; }\
+.reactions { margin-top: 4px; }\
+.reaction { display: inline-block; background: #2f3136; border-radius: 8px; padding: 2px 6px; margin-right: 4px; }\
+.emoji { width: 16px; height: 16px; vertical-align: middle; }";
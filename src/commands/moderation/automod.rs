@@ -0,0 +1,793 @@
+use super::{
+    cases::{create_case, CaseAction},
+    serverlist::INVITE_ID_REGEX,
+    warnings::add_warning,
+};
+use crate::core::{
+    checks::is_moderator,
+    constants::{ERROR_COLOR, MAIN_COLOR},
+    guild_config::{on_off_str, parse_on_off, update_guild_config, GuildConfigs},
+    moderation::timeout_member,
+    storage::get_store,
+    util::{format_duration, parse_duration},
+};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use log::{error, info};
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{channel::Message, event::MessageUpdateEvent, id::GuildId},
+    prelude::Context,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+// Messages with less letters than this are never treated as excessive caps
+const CAPS_MIN_LETTERS: usize = 10;
+// Duplicate messages only count as spam when sent within this timespan
+const DUPLICATE_WINDOW_SECS: i64 = 60;
+// Seconds until the automod notice in the channel is removed again
+const NOTICE_LIFETIME_SECS: u64 = 10;
+
+lazy_static! {
+    // Regex to parse the domain of links in messages
+    static ref LINK_DOMAIN_REGEX: Regex = Regex::new(r"https?://(?:www\.)?([^/\s:]+)").unwrap();
+
+    // The last message of each user per guild, with how often it has been repeated
+    static ref RECENT_MESSAGES: Mutex<HashMap<(u64, u64), RecentMessage>> = Mutex::new(HashMap::new());
+
+    // Compiled banned patterns per guild
+    static ref PATTERN_SETS: Mutex<HashMap<u64, CompiledPatterns>> = Mutex::new(HashMap::new());
+}
+
+// A compiled pattern set along with the patterns it was compiled from
+type CompiledPatterns = (Vec<String>, Arc<RegexSet>);
+
+struct RecentMessage {
+    content: String,
+    count: usize,
+    last_sent: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct AutomodData {
+    guilds: HashMap<u64, AutomodConfig>,
+}
+
+impl GuildConfigs for AutomodData {
+    type Config = AutomodConfig;
+
+    fn configs_mut(&mut self) -> &mut HashMap<u64, AutomodConfig> {
+        &mut self.guilds
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AutomodConfig {
+    enabled: bool,
+    banned_words: Vec<String>,
+    banned_patterns: Vec<String>,
+    block_invites: bool,
+    max_mentions: Option<usize>,
+    max_caps_percent: Option<usize>,
+    max_duplicates: Option<usize>,
+    block_links: bool,
+    allowed_domains: Vec<String>,
+    actions: HashMap<AutomodRule, AutomodAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+enum AutomodRule {
+    Words,
+    Invites,
+    Mentions,
+    Caps,
+    Spam,
+    Links,
+}
+
+impl AutomodRule {
+    fn parse(name: &str) -> Option<AutomodRule> {
+        match name.to_lowercase().as_str() {
+            "words" | "word" | "regex" => Some(AutomodRule::Words),
+            "invites" | "invite" => Some(AutomodRule::Invites),
+            "mentions" | "mention" => Some(AutomodRule::Mentions),
+            "caps" => Some(AutomodRule::Caps),
+            "spam" | "duplicates" => Some(AutomodRule::Spam),
+            "links" | "link" => Some(AutomodRule::Links),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &str {
+        match self {
+            AutomodRule::Words => "Using a banned word",
+            AutomodRule::Invites => "Posting an invite link",
+            AutomodRule::Mentions => "Mass mentioning",
+            AutomodRule::Caps => "Excessive caps",
+            AutomodRule::Spam => "Duplicate spam",
+            AutomodRule::Links => "Posting a link that isn't allowed",
+        }
+    }
+}
+
+/// What happens to a message violating a rule. The message is deleted in any case.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+enum AutomodAction {
+    Delete,
+    Warn,
+    Timeout { seconds: i64 },
+}
+
+impl AutomodAction {
+    fn describe(&self) -> String {
+        match self {
+            AutomodAction::Delete => "delete".to_string(),
+            AutomodAction::Warn => "delete and warn".to_string(),
+            AutomodAction::Timeout { seconds } => format!(
+                "delete and timeout for {}",
+                format_duration(Duration::seconds(*seconds))
+            ),
+        }
+    }
+}
+
+#[command]
+#[sub_commands(
+    automod_enable,
+    automod_disable,
+    automod_word,
+    automod_regex,
+    automod_invites,
+    automod_mentions,
+    automod_caps,
+    automod_spam,
+    automod_links,
+    automod_allow,
+    automod_action
+)]
+#[description(
+    "Shows the auto-moderation settings of this server. \
+    Refer to the sub-commands for setting up the rules. \
+    Moderators are never affected by auto-moderation."
+)]
+pub async fn automod(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let fields = {
+        let store = get_store::<AutomodData>(ctx).await;
+        let store = store.read().await;
+        let default_config = AutomodConfig::default();
+        let config = store.guilds.get(&guild_id.0).unwrap_or(&default_config);
+
+        let action_of = |rule: AutomodRule| {
+            config
+                .actions
+                .get(&rule)
+                .unwrap_or(&AutomodAction::Delete)
+                .describe()
+        };
+        let or_off = |value: Option<usize>, unit: &str| match value {
+            Some(value) => format!("max. {}{}", value, unit),
+            None => "off".to_string(),
+        };
+        let on_off = |value: bool| match value {
+            true => "on".to_string(),
+            false => "off".to_string(),
+        };
+        let list_or_none = |list: &Vec<String>| match list.is_empty() {
+            true => "none".to_string(),
+            false => list
+                .iter()
+                .map(|i| format!("`{}`", i))
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+
+        vec![
+            ("Status", on_off(config.enabled), false),
+            (
+                "Banned words",
+                format!(
+                    "{}\n**Patterns**: {}\n**Action**: {}",
+                    list_or_none(&config.banned_words),
+                    list_or_none(&config.banned_patterns),
+                    action_of(AutomodRule::Words)
+                ),
+                false,
+            ),
+            (
+                "Invites",
+                format!(
+                    "{}\n**Action**: {}",
+                    on_off(config.block_invites),
+                    action_of(AutomodRule::Invites)
+                ),
+                true,
+            ),
+            (
+                "Mentions",
+                format!(
+                    "{}\n**Action**: {}",
+                    or_off(config.max_mentions, ""),
+                    action_of(AutomodRule::Mentions)
+                ),
+                true,
+            ),
+            (
+                "Caps",
+                format!(
+                    "{}\n**Action**: {}",
+                    or_off(config.max_caps_percent, "%"),
+                    action_of(AutomodRule::Caps)
+                ),
+                true,
+            ),
+            (
+                "Duplicate spam",
+                format!(
+                    "{}\n**Action**: {}",
+                    or_off(config.max_duplicates, " repeats"),
+                    action_of(AutomodRule::Spam)
+                ),
+                true,
+            ),
+            (
+                "Links",
+                format!(
+                    "{}\n**Allowed**: {}\n**Action**: {}",
+                    on_off(config.block_links),
+                    list_or_none(&config.allowed_domains),
+                    action_of(AutomodRule::Links)
+                ),
+                true,
+            ),
+        ]
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Auto-moderation settings")
+                    .fields(fields)
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("enable")]
+#[aliases("on")]
+#[description("Enables auto-moderation on this server")]
+async fn automod_enable(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.enabled = true;
+        Ok("Enabled auto-moderation".to_string())
+    })
+    .await
+}
+
+#[command("disable")]
+#[aliases("off")]
+#[description("Disables auto-moderation on this server")]
+async fn automod_disable(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.enabled = false;
+        Ok("Disabled auto-moderation".to_string())
+    })
+    .await
+}
+
+#[command("word")]
+#[description("Adds or removes a banned word. Words are matched case-insensitively.")]
+#[usage("<add|remove> <word>")]
+#[example("add badword")]
+#[min_args(2)]
+async fn automod_word(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let operation = args.single::<String>()?;
+    let word = args.rest().to_lowercase();
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        update_list(&mut config.banned_words, &operation, &word, "banned words")
+    })
+    .await
+}
+
+#[command("regex")]
+#[description("Adds or removes a banned regular expression pattern")]
+#[usage("<add|remove> <pattern>")]
+#[example("add fr[e3]{2} n[i1]tro")]
+#[min_args(2)]
+async fn automod_regex(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let operation = args.single::<String>()?;
+    let pattern = args.rest().to_string();
+
+    if Regex::new(&pattern).is_err() {
+        return Err(CommandError::from("The regex pattern is invalid"));
+    }
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        update_list(
+            &mut config.banned_patterns,
+            &operation,
+            &pattern,
+            "banned patterns",
+        )
+    })
+    .await?;
+
+    // Compile the new patterns right away instead of on the next message
+    let guild_id = msg.guild_id.unwrap();
+    let store = get_store::<AutomodData>(ctx).await;
+    let store = store.read().await;
+    if let Some(config) = store.guilds.get(&guild_id.0) {
+        get_pattern_set(guild_id, &config.banned_patterns);
+    }
+
+    Ok(())
+}
+
+#[command("invites")]
+#[description("Blocks or allows discord invite links")]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn automod_invites(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let block = parse_on_off(&mut args)?;
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.block_invites = block;
+        Ok(format!("Invite blocking is now **{}**", on_off_str(block)))
+    })
+    .await
+}
+
+#[command("mentions")]
+#[description("Sets the maximum amount of user and role mentions per message")]
+#[usage("<max mentions|off>")]
+#[example("5")]
+#[min_args(1)]
+async fn automod_mentions(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let max = parse_limit(&mut args, usize::MAX)?;
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.max_mentions = max;
+        Ok(format!("Mention limit is now **{}**", limit_str(max, "")))
+    })
+    .await
+}
+
+#[command("caps")]
+#[description(
+    "Sets the maximum percentage of uppercase letters per message. \
+    Only messages with at least 10 letters are checked."
+)]
+#[usage("<max percent|off>")]
+#[example("70")]
+#[min_args(1)]
+async fn automod_caps(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let max = parse_limit(&mut args, 100)?;
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.max_caps_percent = max;
+        Ok(format!("Caps limit is now **{}**", limit_str(max, "%")))
+    })
+    .await
+}
+
+#[command("spam")]
+#[description(
+    "Sets how often a member may repeat the same message within a minute before it's removed"
+)]
+#[usage("<max repeats|off>")]
+#[example("3")]
+#[min_args(1)]
+async fn automod_spam(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let max = parse_limit(&mut args, usize::MAX)?;
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.max_duplicates = max;
+        Ok(format!(
+            "Duplicate limit is now **{}**",
+            limit_str(max, " repeats")
+        ))
+    })
+    .await
+}
+
+#[command("links")]
+#[description("Blocks or allows links, except for domains on the allowlist")]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn automod_links(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let block = parse_on_off(&mut args)?;
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        config.block_links = block;
+        Ok(format!("Link blocking is now **{}**", on_off_str(block)))
+    })
+    .await
+}
+
+#[command("allow")]
+#[description("Adds or removes a domain to the link allowlist. Subdomains are allowed as well.")]
+#[usage("<add|remove> <domain>")]
+#[example("add youtube.com")]
+#[min_args(2)]
+async fn automod_allow(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let operation = args.single::<String>()?;
+    let domain = args.single::<String>()?.to_lowercase();
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        update_list(
+            &mut config.allowed_domains,
+            &operation,
+            &domain,
+            "allowed domains",
+        )
+    })
+    .await
+}
+
+#[command("action")]
+#[description(
+    "Sets what happens when a message violates a rule. Violating messages are always deleted, \
+    but the member can additionally be warned or timed out.\n\
+    Rules are `words`, `invites`, `mentions`, `caps`, `spam` and `links`."
+)]
+#[usage("<rule> <delete|warn|timeout <duration>>")]
+#[example("invites warn")]
+#[example("spam timeout 10m")]
+#[min_args(2)]
+async fn automod_action(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let rule = AutomodRule::parse(&args.single::<String>()?).ok_or_else(|| {
+        CommandError::from(
+            "The rule must be one of `words`, `invites`, `mentions`, `caps`, `spam` or `links`",
+        )
+    })?;
+
+    let action = match args.single::<String>()?.to_lowercase().as_str() {
+        "delete" => AutomodAction::Delete,
+        "warn" => AutomodAction::Warn,
+        "timeout" | "mute" => {
+            let duration = args
+                .single::<String>()
+                .ok()
+                .and_then(|d| parse_duration(&d))
+                .filter(|d| *d <= Duration::days(28))
+                .ok_or_else(|| {
+                    CommandError::from("Please supply a valid timeout duration of up to 28 days")
+                })?;
+
+            AutomodAction::Timeout {
+                seconds: duration.num_seconds(),
+            }
+        }
+        _ => {
+            return Err(CommandError::from(
+                "The action must be one of `delete`, `warn` or `timeout <duration>`",
+            ))
+        }
+    };
+
+    update_guild_config::<AutomodData, _>(ctx, msg, |config| {
+        let description = format!(
+            "{} will now result in: **{}**",
+            rule.describe(),
+            action.describe()
+        );
+        config.actions.insert(rule, action);
+        Ok(description)
+    })
+    .await
+}
+
+/// Checks a message against the auto-moderation rules of its guild and punishes violations
+pub async fn check_message(ctx: &Context, msg: &Message) {
+    check(ctx, msg, false).await;
+}
+
+/// Checks the new content of an edited message, so filtered content can't be edited in afterwards
+pub async fn check_message_edit(ctx: &Context, event: &MessageUpdateEvent) {
+    // Edits without new content are i.e. embeds that discord resolved for a link
+    let guild_id = match event.guild_id {
+        Some(guild_id) if event.content.is_some() => guild_id,
+        _ => return,
+    };
+
+    // The edited message has to be fetched, which is only worth it where automod is enabled
+    let is_enabled = {
+        let store = get_store::<AutomodData>(ctx).await;
+        let store = store.read().await;
+        store.guilds.get(&guild_id.0).is_some_and(|c| c.enabled)
+    };
+    if !is_enabled {
+        return;
+    }
+
+    let mut msg = match event.channel_id.message(&ctx.http, event.id).await {
+        Ok(msg) => msg,
+        Err(_) => return,
+    };
+    // Messages requested over http don't know their guild
+    msg.guild_id = Some(guild_id);
+
+    check(ctx, &msg, true).await;
+}
+
+async fn check(ctx: &Context, msg: &Message, is_edit: bool) {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    if msg.author.bot {
+        return;
+    }
+
+    let violation = {
+        let store = get_store::<AutomodData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0) {
+            Some(config) if config.enabled => {
+                find_violation(config, guild_id, msg, is_edit).map(|rule| {
+                    let action = config
+                        .actions
+                        .get(&rule)
+                        .cloned()
+                        .unwrap_or(AutomodAction::Delete);
+                    (rule, action)
+                })
+            }
+            _ => None,
+        }
+    };
+
+    let (rule, action) = match violation {
+        Some(violation) => violation,
+        None => return,
+    };
+
+    // Checking for moderators needs the full member, so only do it once a rule has been violated
+    if let Ok(member) = msg.member(ctx).await {
+        if is_moderator(ctx, &member).await {
+            return;
+        }
+    }
+
+    info!(
+        "Automod rule \"{}\" triggered by {} in guild {}",
+        rule.describe(),
+        msg.author.id,
+        guild_id
+    );
+
+    let _ = msg.delete(ctx).await;
+
+    let reason = format!("Automod: {}", rule.describe());
+    let bot_id = ctx.cache.current_user_id().await;
+
+    match action {
+        AutomodAction::Delete => (),
+        AutomodAction::Warn => {
+            add_warning(ctx, guild_id, msg.author.id, bot_id, &reason).await;
+        }
+        AutomodAction::Timeout { seconds } => {
+            let duration = Duration::seconds(seconds);
+
+            match timeout_member(ctx, guild_id, msg.author.id, Some(Utc::now() + duration)).await {
+                Ok(()) => {
+                    create_case(
+                        ctx,
+                        guild_id,
+                        CaseAction::Timeout,
+                        msg.author.id,
+                        bot_id,
+                        &reason,
+                        Some(duration),
+                    )
+                    .await;
+                }
+                Err(why) => error!("Couldn't time out {}: {:?}", msg.author.id, why),
+            }
+        }
+    }
+
+    // Let the member know why their message disappeared, and remove the notice after a while
+    let notice = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(ERROR_COLOR).description(format!(
+                    "<@{}>, your message has been removed. Reason: **{}**",
+                    msg.author.id.0,
+                    rule.describe()
+                ))
+            })
+        })
+        .await;
+
+    if let Ok(notice) = notice {
+        let http = ctx.http.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(NOTICE_LIFETIME_SECS)).await;
+            let _ = notice.delete(&http).await;
+        });
+    }
+}
+
+fn find_violation(
+    config: &AutomodConfig,
+    guild_id: GuildId,
+    msg: &Message,
+    is_edit: bool,
+) -> Option<AutomodRule> {
+    let content_lowercase = msg.content.to_lowercase();
+
+    // Banned words have to match whole words, so "class" doesn't trigger on "ass"
+    let has_banned_word = content_lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| config.banned_words.iter().any(|banned| banned == word))
+        || config
+            .banned_words
+            .iter()
+            .any(|banned| banned.contains(' ') && content_lowercase.contains(banned.as_str()));
+
+    let has_banned_pattern = get_pattern_set(guild_id, &config.banned_patterns)
+        .is_some_and(|set| set.is_match(&msg.content));
+
+    if has_banned_word || has_banned_pattern {
+        return Some(AutomodRule::Words);
+    }
+
+    if config.block_invites && INVITE_ID_REGEX.is_match(&msg.content) {
+        return Some(AutomodRule::Invites);
+    }
+
+    if let Some(max_mentions) = config.max_mentions {
+        if msg.mentions.len() + msg.mention_roles.len() > max_mentions {
+            return Some(AutomodRule::Mentions);
+        }
+    }
+
+    if let Some(max_caps_percent) = config.max_caps_percent {
+        let letters: Vec<char> = msg.content.chars().filter(|c| c.is_alphabetic()).collect();
+        let uppercase_count = letters.iter().filter(|c| c.is_uppercase()).count();
+
+        if letters.len() >= CAPS_MIN_LETTERS
+            && uppercase_count * 100 > letters.len() * max_caps_percent
+        {
+            return Some(AutomodRule::Caps);
+        }
+    }
+
+    if config.block_links {
+        let has_forbidden_link = LINK_DOMAIN_REGEX.captures_iter(&msg.content).any(|c| {
+            let domain = c[1].to_lowercase();
+            !config
+                .allowed_domains
+                .iter()
+                .any(|allowed| domain == *allowed || domain.ends_with(&format!(".{}", allowed)))
+        });
+
+        if has_forbidden_link {
+            return Some(AutomodRule::Links);
+        }
+    }
+
+    // An edit isn't another message, so it can't be spam
+    if let (Some(max_duplicates), false) = (config.max_duplicates, is_edit) {
+        if count_duplicates(guild_id, msg) > max_duplicates {
+            return Some(AutomodRule::Spam);
+        }
+    }
+
+    None
+}
+
+/// Returns the compiled banned patterns of a guild, compiling them only when they changed
+fn get_pattern_set(guild_id: GuildId, patterns: &[String]) -> Option<Arc<RegexSet>> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    let mut pattern_sets = PATTERN_SETS.lock().unwrap();
+    match pattern_sets.get(&guild_id.0) {
+        Some((compiled_from, set)) if compiled_from == patterns => Some(set.clone()),
+        _ => {
+            let set = Arc::new(RegexSet::new(patterns).ok()?);
+            pattern_sets.insert(guild_id.0, (patterns.to_vec(), set.clone()));
+            Some(set)
+        }
+    }
+}
+
+/// Returns how often the author has sent this message in a row within the duplicate timespan
+fn count_duplicates(guild_id: GuildId, msg: &Message) -> usize {
+    let mut recent_messages = RECENT_MESSAGES.lock().unwrap();
+    let now = Utc::now();
+    let window = Duration::seconds(DUPLICATE_WINDOW_SECS);
+
+    // Messages outside of the timespan can't count as duplicates anymore
+    recent_messages.retain(|_, recent| now.signed_duration_since(recent.last_sent) < window);
+
+    let recent = recent_messages
+        .entry((guild_id.0, msg.author.id.0))
+        .or_insert_with(|| RecentMessage {
+            content: String::new(),
+            count: 0,
+            last_sent: now,
+        });
+
+    if recent.content == msg.content {
+        recent.count += 1;
+    } else {
+        recent.content = msg.content.clone();
+        recent.count = 1;
+    }
+    recent.last_sent = now;
+
+    recent.count
+}
+
+fn update_list(
+    list: &mut Vec<String>,
+    operation: &str,
+    item: &str,
+    list_name: &str,
+) -> Result<String, CommandError> {
+    match operation.to_lowercase().as_str() {
+        "add" => {
+            if !list.iter().any(|i| i == item) {
+                list.push(item.to_string());
+            }
+            Ok(format!("Added `{}` to the {}", item, list_name))
+        }
+        "remove" => {
+            let count_before = list.len();
+            list.retain(|i| i != item);
+
+            match list.len() < count_before {
+                true => Ok(format!("Removed `{}` from the {}", item, list_name)),
+                false => Err(CommandError::from(format!(
+                    "`{}` isn't on the {}",
+                    item, list_name
+                ))),
+            }
+        }
+        _ => Err(CommandError::from(
+            "The operation must be `add` or `remove`",
+        )),
+    }
+}
+
+fn parse_limit(args: &mut Args, max: usize) -> Result<Option<usize>, CommandError> {
+    let arg = args.single::<String>()?;
+
+    if arg.eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+
+    arg.parse::<usize>()
+        .ok()
+        .filter(|limit| *limit <= max)
+        .map(Some)
+        .ok_or_else(|| CommandError::from("Please supply a valid number or `off`"))
+}
+
+fn limit_str(limit: Option<usize>, unit: &str) -> String {
+    match limit {
+        Some(limit) => format!("max. {}{}", limit, unit),
+        None => "off".to_string(),
+    }
+}
@@ -0,0 +1,572 @@
+use crate::core::{
+    constants::{ERROR_COLOR, MAIN_COLOR},
+    guild_config::{update_guild_config, GuildConfigs},
+    moderation::{check_role_position, check_unprivileged_role},
+    scheduler::{schedule, ActionExecutor, ScheduledAction},
+    storage::get_store,
+    util::{format_duration, is_guild_channel, parse_duration, parse_emoji, reaction_matches},
+};
+use chrono::{Duration, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::{Message, Reaction, ReactionType},
+        guild::Member,
+        id::{ChannelId, GuildId, RoleId, UserId},
+    },
+    prelude::{Context, SerenityError},
+};
+use std::{collections::HashMap, str::FromStr};
+
+const DEFAULT_GATE_EMOJI: &str = "✅";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct AutoroleData {
+    guilds: HashMap<u64, AutoroleConfig>,
+}
+
+impl GuildConfigs for AutoroleData {
+    type Config = AutoroleConfig;
+
+    fn configs_mut(&mut self) -> &mut HashMap<u64, AutoroleConfig> {
+        &mut self.guilds
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AutoroleConfig {
+    role_ids: Vec<u64>,
+    delay_seconds: Option<i64>,
+    gate: Option<JoinGate>,
+}
+
+/// A rules message that new members have to react to before they get their auto-roles
+#[derive(Serialize, Deserialize, Clone)]
+struct JoinGate {
+    channel_id: u64,
+    message_id: u64,
+    emoji: String,
+    restricted_role_id: Option<u64>,
+    min_account_age_seconds: Option<i64>,
+}
+
+#[command]
+#[sub_commands(autorole_add, autorole_remove, autorole_delay)]
+#[description(
+    "Shows the roles that new members get automatically. \
+    If a join gate is set up with `gate`, the roles are given once the member passed the gate."
+)]
+pub async fn autorole(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let fields = {
+        let store = get_store::<AutoroleData>(ctx).await;
+        let store = store.read().await;
+        let default_config = AutoroleConfig::default();
+        let config = store.guilds.get(&guild_id.0).unwrap_or(&default_config);
+
+        vec![
+            (
+                "Roles",
+                match config.role_ids.is_empty() {
+                    true => "none".to_string(),
+                    false => config
+                        .role_ids
+                        .iter()
+                        .map(|r| format!("<@&{}>", r))
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                },
+                false,
+            ),
+            (
+                "Delay",
+                match config.delay_seconds {
+                    Some(seconds) => format_duration(Duration::seconds(seconds)),
+                    None => "none".to_string(),
+                },
+                true,
+            ),
+            (
+                "Join gate",
+                match &config.gate {
+                    Some(gate) => format!(
+                        "[Rules message](https://discord.com/channels/{}/{}/{})",
+                        guild_id.0, gate.channel_id, gate.message_id
+                    ),
+                    None => "off".to_string(),
+                },
+                true,
+            ),
+        ]
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).title("Auto-roles").fields(fields))
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("add")]
+#[description("Adds a role that new members get automatically")]
+#[usage("<role>")]
+#[example("@Member")]
+#[min_args(1)]
+async fn autorole_add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let role_id = parse_role_arg(&mut args)?;
+    check_automatic_role(ctx, msg, role_id).await?;
+
+    update_guild_config::<AutoroleData, _>(ctx, msg, |config| {
+        if !config.role_ids.contains(&role_id.0) {
+            config.role_ids.push(role_id.0);
+        }
+        Ok(format!("New members now get <@&{}>", role_id.0))
+    })
+    .await
+}
+
+#[command("remove")]
+#[description("Removes a role from the auto-roles")]
+#[usage("<role>")]
+#[example("@Member")]
+#[min_args(1)]
+async fn autorole_remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let role_id = parse_role_arg(&mut args)?;
+
+    update_guild_config::<AutoroleData, _>(ctx, msg, |config| {
+        let count_before = config.role_ids.len();
+        config.role_ids.retain(|r| *r != role_id.0);
+
+        match config.role_ids.len() < count_before {
+            true => Ok(format!("New members don't get <@&{}> anymore", role_id.0)),
+            false => Err(CommandError::from("That role isn't an auto-role")),
+        }
+    })
+    .await
+}
+
+#[command("delay")]
+#[description(
+    "Sets how long after joining new members get their auto-roles. \
+    Doesn't apply if a join gate is set up."
+)]
+#[usage("<duration|off>")]
+#[example("10m")]
+#[min_args(1)]
+async fn autorole_delay(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let delay = parse_optional_duration(&mut args)?;
+
+    update_guild_config::<AutoroleData, _>(ctx, msg, |config| {
+        config.delay_seconds = delay.map(|d| d.num_seconds());
+        Ok(match delay {
+            Some(delay) => format!(
+                "Auto-roles are given **{}** after joining",
+                format_duration(delay)
+            ),
+            None => "Auto-roles are given right after joining".to_string(),
+        })
+    })
+    .await
+}
+
+#[command]
+#[sub_commands(gate_setup, gate_role, gate_age, gate_off)]
+#[description(
+    "Shows the join gate settings. With a join gate, new members have to react to a rules message \
+    before they get their auto-roles. Until then, they can be kept in a restricted role."
+)]
+pub async fn gate(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let gate = {
+        let store = get_store::<AutoroleData>(ctx).await;
+        let store = store.read().await;
+        store.guilds.get(&guild_id.0).and_then(|c| c.gate.clone())
+    };
+
+    let gate = gate.ok_or_else(|| {
+        CommandError::from("There's no join gate. Use `gate setup` to create one.")
+    })?;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Join gate")
+                    .field(
+                        "Rules message",
+                        format!(
+                            "[Jump to message](https://discord.com/channels/{}/{}/{})",
+                            guild_id.0, gate.channel_id, gate.message_id
+                        ),
+                        true,
+                    )
+                    .field("Emoji", &gate.emoji, true)
+                    .field(
+                        "Restricted role",
+                        match gate.restricted_role_id {
+                            Some(role_id) => format!("<@&{}>", role_id),
+                            None => "none".to_string(),
+                        },
+                        true,
+                    )
+                    .field(
+                        "Minimum account age",
+                        match gate.min_account_age_seconds {
+                            Some(seconds) => format_duration(Duration::seconds(seconds)),
+                            None => "none".to_string(),
+                        },
+                        true,
+                    )
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("setup")]
+#[description(
+    "Posts the rules message of the join gate to the channel. \
+    Members pass the gate by reacting with the emoji (✅ by default)."
+)]
+#[usage("<#channel> [emoji] [rules text]")]
+#[example("#rules")]
+#[example("#rules 👍 Be nice to each other. React with 👍 to get access to the server.")]
+#[min_args(1)]
+async fn gate_setup(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let channel_id = args
+        .single::<ChannelId>()
+        .map_err(|_| CommandError::from("Please supply a valid channel mention or id"))?;
+
+    if !is_guild_channel(ctx, guild_id, channel_id).await {
+        return Err(CommandError::from("Please supply a channel of this server"));
+    }
+
+    let emoji = match args.current().and_then(parse_emoji) {
+        Some(emoji) => {
+            args.advance();
+            emoji
+        }
+        None => ReactionType::Unicode(DEFAULT_GATE_EMOJI.to_string()),
+    };
+
+    let text = match args.rest() {
+        "" => format!(
+            "Please read the rules of this server. \
+            Once you're done, react with {} to get access to the server.",
+            emoji
+        ),
+        text => text.to_string(),
+    };
+
+    let rules_message = channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).title("Rules").description(text))
+        })
+        .await?;
+    rules_message
+        .react(&ctx.http, emoji.clone())
+        .await
+        .map_err(|_| CommandError::from("I can't react with that emoji"))?;
+
+    {
+        let store = get_store::<AutoroleData>(ctx).await;
+        let mut store = store.write().await;
+        let config = store.guilds.entry(guild_id.0).or_default();
+
+        let (restricted_role_id, min_account_age_seconds) = match &config.gate {
+            Some(gate) => (gate.restricted_role_id, gate.min_account_age_seconds),
+            None => (None, None),
+        };
+
+        config.gate = Some(JoinGate {
+            channel_id: channel_id.0,
+            message_id: rules_message.id.0,
+            emoji: emoji.to_string(),
+            restricted_role_id,
+            min_account_age_seconds,
+        });
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Created the join gate in <#{}>. New members now get their auto-roles \
+                    after reacting with {}",
+                    channel_id.0, emoji
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("role")]
+#[description(
+    "Sets a role that new members are kept in until they passed the join gate. \
+    Use it to restrict which channels they can see."
+)]
+#[usage("<role|off>")]
+#[example("@Unverified")]
+#[min_args(1)]
+async fn gate_role(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let role_id = match args.current() {
+        Some("off") => None,
+        _ => Some(parse_role_arg(&mut args)?),
+    };
+    if let Some(role_id) = role_id {
+        check_automatic_role(ctx, msg, role_id).await?;
+    }
+
+    update_gate(ctx, msg, |gate| {
+        gate.restricted_role_id = role_id.map(|r| r.0);
+        match role_id {
+            Some(role_id) => format!(
+                "New members are kept in <@&{}> until they passed the gate",
+                role_id.0
+            ),
+            None => "New members aren't restricted until they passed the gate".to_string(),
+        }
+    })
+    .await
+}
+
+#[command("age")]
+#[description("Sets how old an account has to be to pass the join gate")]
+#[usage("<account age|off>")]
+#[example("7d")]
+#[min_args(1)]
+async fn gate_age(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let age = parse_optional_duration(&mut args)?;
+
+    update_gate(ctx, msg, |gate| {
+        gate.min_account_age_seconds = age.map(|a| a.num_seconds());
+        match age {
+            Some(age) => format!(
+                "Accounts have to be at least **{}** old to pass the gate",
+                format_duration(age)
+            ),
+            None => "Accounts of any age can pass the gate".to_string(),
+        }
+    })
+    .await
+}
+
+#[command("off")]
+#[aliases("remove")]
+#[description(
+    "Removes the join gate, so new members get their auto-roles right away. \
+    The rules message isn't deleted."
+)]
+async fn gate_off(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    update_guild_config::<AutoroleData, _>(ctx, msg, |config| match config.gate.take() {
+        Some(_) => Ok("Removed the join gate".to_string()),
+        None => Err(CommandError::from("There's no join gate")),
+    })
+    .await
+}
+
+/// Gives new members their auto-roles, or the restricted role if there's a join gate
+pub async fn handle_member_join(ctx: &Context, guild_id: GuildId, member: &Member) {
+    let (role_ids, delay_seconds, gate) = {
+        let store = get_store::<AutoroleData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0) {
+            Some(config) => (
+                config.role_ids.clone(),
+                config.delay_seconds,
+                config.gate.clone(),
+            ),
+            None => return,
+        }
+    };
+
+    if let Some(gate) = gate {
+        if let Some(role_id) = gate.restricted_role_id {
+            if let Err(why) = ctx
+                .http
+                .add_member_role(guild_id.0, member.user.id.0, role_id)
+                .await
+            {
+                error!(
+                    "Couldn't add restricted role to {}: {:?}",
+                    member.user.id, why
+                );
+            }
+        }
+        return;
+    }
+
+    match delay_seconds {
+        Some(seconds) => {
+            schedule(
+                ctx,
+                Utc::now() + Duration::seconds(seconds),
+                ScheduledAction::AddRoles {
+                    guild_id: guild_id.0,
+                    user_id: member.user.id.0,
+                    role_ids,
+                },
+            )
+            .await;
+        }
+        None => add_roles(ctx, guild_id, member.user.id, &role_ids).await,
+    }
+}
+
+/// Lets members pass the join gate when they react to its rules message
+pub async fn handle_gate_reaction(ctx: &Context, reaction: &Reaction) {
+    let (guild_id, user_id) = match (reaction.guild_id, reaction.user_id) {
+        (Some(guild_id), Some(user_id)) => (guild_id, user_id),
+        _ => return,
+    };
+
+    let (role_ids, gate) = {
+        let store = get_store::<AutoroleData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0) {
+            Some(AutoroleConfig {
+                role_ids,
+                gate: Some(gate),
+                ..
+            }) if gate.message_id == reaction.message_id.0 => (role_ids.clone(), gate.clone()),
+            _ => return,
+        }
+    };
+
+    let is_gate_emoji = ReactionType::from_str(&gate.emoji)
+        .is_ok_and(|emoji| reaction_matches(&emoji, &reaction.emoji));
+
+    if !is_gate_emoji || user_id == ctx.cache.current_user_id().await {
+        return;
+    }
+
+    if let Some(seconds) = gate.min_account_age_seconds {
+        let account_age = Utc::now().signed_duration_since(user_id.created_at());
+
+        if account_age < Duration::seconds(seconds) {
+            // Remove the reaction, so the member can try again once the account is old enough
+            let _ = reaction.delete(ctx).await;
+
+            let guild_name = guild_id.name(&ctx.cache).await.unwrap_or_default();
+
+            if let Ok(user) = user_id.to_user(ctx).await {
+                let _ = user
+                    .direct_message(ctx, |m| {
+                        m.embed(|e| {
+                            e.colour(ERROR_COLOR).description(format!(
+                                "Your account is too new to get access to **{}**. \
+                                Please try again in {}.",
+                                guild_name,
+                                format_duration(Duration::seconds(seconds) - account_age)
+                            ))
+                        })
+                    })
+                    .await;
+            }
+            return;
+        }
+    }
+
+    if let Some(role_id) = gate.restricted_role_id {
+        let _ = ctx
+            .http
+            .remove_member_role(guild_id.0, user_id.0, role_id)
+            .await;
+    }
+
+    add_roles(ctx, guild_id, user_id, &role_ids).await;
+}
+
+async fn add_roles(ctx: &Context, guild_id: GuildId, user_id: UserId, role_ids: &[u64]) {
+    for role_id in role_ids {
+        if let Err(why) = ctx
+            .http
+            .add_member_role(guild_id.0, user_id.0, *role_id)
+            .await
+        {
+            error!(
+                "Couldn't add auto-role {} to {}: {:?}",
+                role_id, user_id, why
+            );
+        }
+    }
+}
+
+async fn update_gate<F>(ctx: &Context, msg: &Message, update: F) -> CommandResult
+where
+    F: FnOnce(&mut JoinGate) -> String,
+{
+    update_guild_config::<AutoroleData, _>(ctx, msg, |config| match &mut config.gate {
+        Some(gate) => Ok(update(gate)),
+        None => Err(CommandError::from(
+            "There's no join gate. Use `gate setup` to create one.",
+        )),
+    })
+    .await
+}
+
+/// Everyone who joins gets these roles, so they have to be below the moderator
+/// and can't come with moderation permissions
+async fn check_automatic_role(ctx: &Context, msg: &Message, role_id: RoleId) -> CommandResult {
+    let role = check_role_position(ctx, msg.guild_id.unwrap(), msg.author.id, role_id).await?;
+    check_unprivileged_role(&role)?;
+
+    Ok(())
+}
+
+fn parse_role_arg(args: &mut Args) -> Result<RoleId, CommandError> {
+    args.single::<RoleId>()
+        .map_err(|_| CommandError::from("Please supply a valid role mention or id"))
+}
+
+fn parse_optional_duration(args: &mut Args) -> Result<Option<Duration>, CommandError> {
+    let arg = args.single::<String>()?;
+
+    match arg.eq_ignore_ascii_case("off") {
+        true => Ok(None),
+        false => parse_duration(&arg)
+            .map(Some)
+            .ok_or_else(|| CommandError::from("Please supply a valid duration or `off`")),
+    }
+}
+
+/// Gives delayed autoroles to members once their delay has passed
+pub struct AddRolesExecutor;
+
+#[async_trait]
+impl ActionExecutor for AddRolesExecutor {
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError> {
+        if let ScheduledAction::AddRoles {
+            guild_id,
+            user_id,
+            role_ids,
+        } = action
+        {
+            for role_id in role_ids {
+                ctx.http
+                    .add_member_role(*guild_id, *user_id, *role_id)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+use crate::core::{
+    constants::{ERROR_COLOR, MAIN_COLOR},
+    moderation::DEFAULT_REASON,
+    storage::get_store,
+    util::{format_duration, is_guild_channel},
+};
+use chrono::{DateTime, Duration, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        guild::{Action, ActionMember},
+        id::{ChannelId, GuildId, UserId},
+    },
+    prelude::Context,
+};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CaseData {
+    guilds: HashMap<u64, GuildCases>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct GuildCases {
+    log_channel_id: Option<u64>,
+    next_number: u64,
+    cases: Vec<Case>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Case {
+    number: u64,
+    action: CaseAction,
+    user_id: u64,
+    moderator_id: u64,
+    reason: String,
+    duration_seconds: Option<i64>,
+    timestamp: DateTime<Utc>,
+    log_message_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseAction {
+    Warn,
+    Timeout,
+    Untimeout,
+    Mute,
+    Kick,
+    Softban,
+    Tempban,
+    Ban,
+    Unban,
+}
+
+impl CaseAction {
+    fn name(&self) -> &str {
+        match self {
+            CaseAction::Warn => "Warn",
+            CaseAction::Timeout => "Timeout",
+            CaseAction::Untimeout => "Timeout lifted",
+            CaseAction::Mute => "Mute",
+            CaseAction::Kick => "Kick",
+            CaseAction::Softban => "Softban",
+            CaseAction::Tempban => "Tempban",
+            CaseAction::Ban => "Ban",
+            CaseAction::Unban => "Unban",
+        }
+    }
+}
+
+#[command]
+#[description(
+    "Sets the channel that moderation cases are posted to. \
+    Use `off` to stop logging cases, or leave out the channel to see the current one."
+)]
+#[usage("[#channel|off]")]
+#[example("#mod-log")]
+#[example("off")]
+pub async fn modlog(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let new_channel = match args.current() {
+        None => None,
+        Some("off") => Some(None),
+        Some(_) => Some(Some(args.single::<ChannelId>().map_err(|_| {
+            CommandError::from("Please supply a valid channel mention or id")
+        })?)),
+    };
+
+    if let Some(Some(channel_id)) = new_channel {
+        if !is_guild_channel(ctx, guild_id, channel_id).await {
+            return Err(CommandError::from("Please supply a channel of this server"));
+        }
+    }
+
+    let log_channel_id = {
+        let store = get_store::<CaseData>(ctx).await;
+        let mut store = store.write().await;
+        let guild_cases = store.guilds.entry(guild_id.0).or_default();
+
+        if let Some(channel_id) = new_channel {
+            guild_cases.log_channel_id = channel_id.map(|c| c.0);
+            store.save();
+        }
+
+        store.guilds[&guild_id.0].log_channel_id
+    };
+
+    let description = match log_channel_id {
+        Some(channel_id) => format!("Moderation cases are logged to <#{}>", channel_id),
+        None => "Moderation cases aren't logged to any channel".to_string(),
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).description(description))
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Shows the details of a moderation case")]
+#[usage("<case number>")]
+#[example("42")]
+#[min_args(1)]
+pub async fn case(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let number = parse_case_number(&mut args)?;
+
+    let case = {
+        let store = get_store::<CaseData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .guilds
+            .get(&guild_id.0)
+            .and_then(|g| g.cases.iter().find(|c| c.number == number))
+            .cloned()
+            .ok_or_else(|| CommandError::from(format!("There's no case #{}", number)))?
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| m.set_embed(create_case_embed(&case)))
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Changes the reason of a moderation case and updates its log message")]
+#[usage("<case number> <reason>")]
+#[example("42 Posting scam links")]
+#[min_args(2)]
+pub async fn reason(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let number = parse_case_number(&mut args)?;
+    let reason = args.rest();
+
+    let (case, log_channel_id) = {
+        let store = get_store::<CaseData>(ctx).await;
+        let mut store = store.write().await;
+
+        let guild_cases = store.guilds.entry(guild_id.0).or_default();
+        let log_channel_id = guild_cases.log_channel_id;
+        let case = guild_cases
+            .cases
+            .iter_mut()
+            .find(|c| c.number == number)
+            .ok_or_else(|| CommandError::from(format!("There's no case #{}", number)))?;
+
+        case.reason = reason.to_string();
+        let case = case.clone();
+
+        store.save();
+        (case, log_channel_id)
+    };
+
+    // Update the log message, if the case has been logged to the current log channel
+    if let (Some(channel_id), Some(message_id)) = (log_channel_id, case.log_message_id) {
+        if let Ok(mut log_message) = ChannelId(channel_id).message(&ctx.http, message_id).await {
+            let _ = log_message
+                .edit(&ctx, |m| m.set_embed(create_case_embed(&case)))
+                .await;
+        }
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description(format!("Updated the reason of case #{}", number))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Records a new moderation case and posts it to the guild's mod-log channel, if one is set.
+/// Returns the number of the new case.
+pub async fn create_case(
+    ctx: &Context,
+    guild_id: GuildId,
+    action: CaseAction,
+    user_id: UserId,
+    moderator_id: UserId,
+    reason: &str,
+    duration: Option<Duration>,
+) -> u64 {
+    let (case, log_channel_id) = {
+        let store = get_store::<CaseData>(ctx).await;
+        let mut store = store.write().await;
+
+        let guild_cases = store.guilds.entry(guild_id.0).or_default();
+        guild_cases.next_number += 1;
+
+        let case = Case {
+            number: guild_cases.next_number,
+            action,
+            user_id: user_id.0,
+            moderator_id: moderator_id.0,
+            reason: reason.to_string(),
+            duration_seconds: duration.map(|d| d.num_seconds()),
+            timestamp: Utc::now(),
+            log_message_id: None,
+        };
+        guild_cases.cases.push(case.clone());
+        let log_channel_id = guild_cases.log_channel_id;
+
+        store.save();
+        (case, log_channel_id)
+    };
+
+    let channel_id = match log_channel_id {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return case.number,
+    };
+
+    let log_message = channel_id
+        .send_message(&ctx.http, |m| m.set_embed(create_case_embed(&case)))
+        .await;
+
+    match log_message {
+        Ok(log_message) => {
+            let store = get_store::<CaseData>(ctx).await;
+            let mut store = store.write().await;
+
+            if let Some(stored_case) = store
+                .guilds
+                .get_mut(&guild_id.0)
+                .and_then(|g| g.cases.iter_mut().find(|c| c.number == case.number))
+            {
+                stored_case.log_message_id = Some(log_message.id.0);
+            }
+
+            store.save();
+        }
+        Err(why) => error!("Couldn't post case #{} to mod-log: {:?}", case.number, why),
+    }
+
+    case.number
+}
+
+/// Creates cases for bans, unbans and kicks that have been done without using the bot,
+/// i.e. through the discord client. The responsible moderator and reason are read from the audit log.
+/// Guilds without a mod-log channel don't get cases for these.
+pub async fn log_external_action(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    audit_action: ActionMember,
+) {
+    let action = match audit_action {
+        ActionMember::BanAdd => CaseAction::Ban,
+        ActionMember::BanRemove => CaseAction::Unban,
+        ActionMember::Kick => CaseAction::Kick,
+        _ => return,
+    };
+
+    // Without a mod-log nobody would see the case, so the audit log isn't requested on every leave
+    let has_log_channel = {
+        let store = get_store::<CaseData>(ctx).await;
+        let store = store.read().await;
+        store
+            .guilds
+            .get(&guild_id.0)
+            .is_some_and(|g| g.log_channel_id.is_some())
+    };
+
+    if !has_log_channel {
+        return;
+    }
+
+    let audit_logs = match guild_id
+        .audit_logs(&ctx.http, Some(audit_action.num()), None, None, Some(10))
+        .await
+    {
+        Ok(audit_logs) => audit_logs,
+        // The bot might not be allowed to view the audit log, in which case there's nothing to log
+        Err(_) => return,
+    };
+
+    // Only consider entries from the last minute, since the events don't reference audit log entries
+    let entry = audit_logs
+        .entries
+        .values()
+        .filter(|e| e.target_id == Some(user_id.0))
+        .filter(|e| matches!(e.action, Action::Member(_)))
+        .filter(|e| Utc::now().signed_duration_since(e.id.created_at()) < Duration::minutes(1))
+        .max_by_key(|e| e.id.0);
+
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    // Actions done with the bot's commands already created their own case
+    if entry.user_id == ctx.cache.current_user_id().await {
+        return;
+    }
+
+    create_case(
+        ctx,
+        guild_id,
+        action,
+        user_id,
+        entry.user_id,
+        entry.reason.as_deref().unwrap_or(DEFAULT_REASON),
+        None,
+    )
+    .await;
+}
+
+fn create_case_embed(case: &Case) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    let colour = match case.action {
+        CaseAction::Kick | CaseAction::Softban | CaseAction::Tempban | CaseAction::Ban => {
+            ERROR_COLOR
+        }
+        _ => MAIN_COLOR,
+    };
+
+    e.colour(colour)
+        .title(format!("Case #{} | {}", case.number, case.action.name()))
+        .field("User", format!("<@{}>", case.user_id), true)
+        .field("Moderator", format!("<@{}>", case.moderator_id), true);
+
+    if let Some(seconds) = case.duration_seconds {
+        e.field(
+            "Duration",
+            format_duration(Duration::seconds(seconds)),
+            true,
+        );
+    }
+
+    // Embed field values are limited to 1024 characters
+    let reason = match case.reason.chars().count() > 1024 {
+        true => format!("{}…", case.reason.chars().take(1023).collect::<String>()),
+        false => case.reason.clone(),
+    };
+
+    e.field("Reason", reason, false)
+        .footer(|f| f.text(format!("User ID: {}", case.user_id)))
+        .timestamp(&case.timestamp);
+
+    e
+}
+
+fn parse_case_number(args: &mut Args) -> Result<u64, CommandError> {
+    args.single::<String>()
+        .ok()
+        .and_then(|n| n.trim_start_matches('#').parse::<u64>().ok())
+        .ok_or_else(|| CommandError::from("Please supply a valid case number"))
+}
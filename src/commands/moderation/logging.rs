@@ -0,0 +1,611 @@
+use crate::core::{
+    constants::{ERROR_COLOR, MAIN_COLOR},
+    storage::get_store,
+    util::is_guild_channel,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::{Channel, GuildChannel, Message},
+        event::MessageUpdateEvent,
+        guild::{Member, Role},
+        id::{ChannelId, GuildId, MessageId, RoleId},
+        user::User,
+    },
+    prelude::Context,
+};
+use std::collections::HashMap;
+
+// Embed field values are limited to 1024 characters, so message contents are cut off before that
+const MAX_LOGGED_CONTENT_CHARS: usize = 1000;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct LoggingData {
+    guilds: HashMap<u64, HashMap<LogEvent, u64>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+enum LogEvent {
+    MessageEdit,
+    MessageDelete,
+    MemberJoin,
+    MemberLeave,
+    MemberUpdate,
+    Roles,
+    Channels,
+}
+
+const ALL_LOG_EVENTS: [LogEvent; 7] = [
+    LogEvent::MessageEdit,
+    LogEvent::MessageDelete,
+    LogEvent::MemberJoin,
+    LogEvent::MemberLeave,
+    LogEvent::MemberUpdate,
+    LogEvent::Roles,
+    LogEvent::Channels,
+];
+
+impl LogEvent {
+    fn parse(name: &str) -> Option<LogEvent> {
+        match name.to_lowercase().as_str() {
+            "edits" | "edit" => Some(LogEvent::MessageEdit),
+            "deletes" | "delete" => Some(LogEvent::MessageDelete),
+            "joins" | "join" => Some(LogEvent::MemberJoin),
+            "leaves" | "leave" => Some(LogEvent::MemberLeave),
+            "members" | "member" => Some(LogEvent::MemberUpdate),
+            "roles" | "role" => Some(LogEvent::Roles),
+            "channels" | "channel" => Some(LogEvent::Channels),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            LogEvent::MessageEdit => "edits",
+            LogEvent::MessageDelete => "deletes",
+            LogEvent::MemberJoin => "joins",
+            LogEvent::MemberLeave => "leaves",
+            LogEvent::MemberUpdate => "members",
+            LogEvent::Roles => "roles",
+            LogEvent::Channels => "channels",
+        }
+    }
+}
+
+#[command]
+#[aliases("logs")]
+#[description(
+    "Sets the channel that server events are logged to. Each event can be logged to its own channel. \
+    Leave out all arguments to see the current log channels.\n\
+    Events are `edits`, `deletes`, `joins`, `leaves`, `members` (nickname and role changes), \
+    `roles`, `channels`, or `all` to set all events at once."
+)]
+#[usage("[<event|all> <#channel|off>]")]
+#[example("")]
+#[example("all #server-log")]
+#[example("deletes #message-log")]
+#[example("joins off")]
+pub async fn logging(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    if args.is_empty() {
+        let log_channels = {
+            let store = get_store::<LoggingData>(ctx).await;
+            let store = store.read().await;
+            store.guilds.get(&guild_id.0).cloned().unwrap_or_default()
+        };
+
+        let description = ALL_LOG_EVENTS
+            .iter()
+            .map(|event| match log_channels.get(event) {
+                Some(channel_id) => format!("**{}**: <#{}>", event.name(), channel_id),
+                None => format!("**{}**: off", event.name()),
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let _ = msg
+            .channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.colour(MAIN_COLOR)
+                        .title("Log channels")
+                        .description(description)
+                })
+            })
+            .await;
+
+        return Ok(());
+    }
+
+    let event_arg = args.single::<String>()?;
+    let events = match event_arg.to_lowercase().as_str() {
+        "all" => ALL_LOG_EVENTS.to_vec(),
+        name => vec![LogEvent::parse(name).ok_or_else(|| {
+            CommandError::from(format!(
+                "Unknown event `{}`. See `~help logging` for the available events",
+                name
+            ))
+        })?],
+    };
+
+    let channel_id = match args.current() {
+        Some("off") => None,
+        _ => Some(args.single::<ChannelId>().map_err(|_| {
+            CommandError::from("Please supply a valid channel mention or id, or `off`")
+        })?),
+    };
+
+    if let Some(channel_id) = channel_id {
+        if !is_guild_channel(ctx, guild_id, channel_id).await {
+            return Err(CommandError::from("Please supply a channel of this server"));
+        }
+    }
+
+    {
+        let store = get_store::<LoggingData>(ctx).await;
+        let mut store = store.write().await;
+        let log_channels = store.guilds.entry(guild_id.0).or_default();
+
+        for event in &events {
+            match channel_id {
+                Some(channel_id) => log_channels.insert(*event, channel_id.0),
+                None => log_channels.remove(event),
+            };
+        }
+
+        store.save();
+    }
+
+    let description = match channel_id {
+        Some(channel_id) => format!("Logging **{}** to <#{}>", event_arg, channel_id.0),
+        None => format!("Stopped logging **{}**", event_arg),
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).description(description))
+        })
+        .await;
+
+    Ok(())
+}
+
+pub async fn log_message_edit(ctx: &Context, old: Option<Message>, event: &MessageUpdateEvent) {
+    let guild_id = match event.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    // Updates without content are i.e. embeds being loaded, which aren't edits
+    let new_content = match &event.content {
+        Some(content) => content,
+        None => return,
+    };
+
+    let author = match (&event.author, &old) {
+        (Some(author), _) => author.clone(),
+        (None, Some(old)) => old.author.clone(),
+        _ => return,
+    };
+
+    if author.bot || old.as_ref().is_some_and(|old| old.content == *new_content) {
+        return;
+    }
+
+    let old_content = match &old {
+        Some(old) => truncate(&old.content),
+        None => "*Message isn't cached*".to_string(),
+    };
+
+    let mut e = create_user_embed(&author);
+    e.colour(MAIN_COLOR)
+        .description(format!(
+            "**Message edited in <#{}>** [Jump to message](https://discord.com/channels/{}/{}/{})",
+            event.channel_id.0, guild_id.0, event.channel_id.0, event.id.0
+        ))
+        .field("Before", or_empty(old_content), false)
+        .field("After", or_empty(truncate(new_content)), false);
+
+    send_log(ctx, guild_id, LogEvent::MessageEdit, e).await;
+}
+
+pub async fn log_message_delete(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    // Deleted messages are still in the cache, as the cache doesn't remove them on deletion
+    let mut e = match ctx.cache.message(channel_id, message_id).await {
+        Some(message) if message.author.bot => return,
+        Some(message) => {
+            let mut e = create_user_embed(&message.author);
+            e.description(format!(
+                "**Message deleted in <#{}>**\n{}",
+                channel_id.0,
+                truncate(&message.content)
+            ));
+
+            if !message.attachments.is_empty() {
+                e.field(
+                    "Attachments",
+                    message
+                        .attachments
+                        .iter()
+                        .map(|a| a.filename.clone())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    false,
+                );
+            }
+            e
+        }
+        None => {
+            let mut e = CreateEmbed::default();
+            e.description(format!(
+                "**Message deleted in <#{}>**\n*Message isn't cached*",
+                channel_id.0
+            ));
+            e
+        }
+    };
+
+    e.colour(ERROR_COLOR)
+        .footer(|f| f.text(format!("Message ID: {}", message_id.0)));
+
+    send_log(ctx, guild_id, LogEvent::MessageDelete, e).await;
+}
+
+pub async fn log_message_delete_bulk(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    message_ids: &[MessageId],
+) {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let mut e = CreateEmbed::default();
+    e.colour(ERROR_COLOR).description(format!(
+        "**{} messages bulk deleted in <#{}>**",
+        message_ids.len(),
+        channel_id.0
+    ));
+
+    send_log(ctx, guild_id, LogEvent::MessageDelete, e).await;
+}
+
+pub async fn log_member_join(ctx: &Context, guild_id: GuildId, member: &Member) {
+    let mut e = create_user_embed(&member.user);
+    e.colour(MAIN_COLOR)
+        .description(format!("**<@{}> joined the server**", member.user.id.0))
+        .field(
+            "Account created",
+            member.user.id.created_at().format("%b %e %Y, %H:%M UTC"),
+            false,
+        );
+
+    send_log(ctx, guild_id, LogEvent::MemberJoin, e).await;
+}
+
+pub async fn log_member_leave(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    member: Option<Member>,
+) {
+    let mut e = create_user_embed(user);
+    e.colour(ERROR_COLOR)
+        .description(format!("**<@{}> left the server**", user.id.0));
+
+    if let Some(member) = member {
+        if let Some(joined_at) = member.joined_at {
+            e.field("Joined", joined_at.format("%b %e %Y, %H:%M UTC"), true);
+        }
+
+        if !member.roles.is_empty() {
+            e.field("Roles", format_roles(&member.roles), true);
+        }
+    }
+
+    send_log(ctx, guild_id, LogEvent::MemberLeave, e).await;
+}
+
+pub async fn log_member_update(ctx: &Context, old: Option<Member>, new: &Member) {
+    // Without the previous state there's nothing to compare against
+    let old = match old {
+        Some(old) => old,
+        None => return,
+    };
+
+    let mut e = create_user_embed(&new.user);
+    let mut changed = false;
+
+    if old.nick != new.nick {
+        e.field(
+            "Nickname",
+            format!(
+                "{} → {}",
+                old.nick.as_deref().unwrap_or("*none*"),
+                new.nick.as_deref().unwrap_or("*none*")
+            ),
+            false,
+        );
+        changed = true;
+    }
+
+    let added_roles: Vec<RoleId> = new
+        .roles
+        .iter()
+        .filter(|r| !old.roles.contains(r))
+        .cloned()
+        .collect();
+    let removed_roles: Vec<RoleId> = old
+        .roles
+        .iter()
+        .filter(|r| !new.roles.contains(r))
+        .cloned()
+        .collect();
+
+    if !added_roles.is_empty() {
+        e.field("Roles added", format_roles(&added_roles), false);
+        changed = true;
+    }
+
+    if !removed_roles.is_empty() {
+        e.field("Roles removed", format_roles(&removed_roles), false);
+        changed = true;
+    }
+
+    if !changed {
+        return;
+    }
+
+    e.colour(MAIN_COLOR)
+        .description(format!("**<@{}> has been updated**", new.user.id.0));
+
+    send_log(ctx, new.guild_id, LogEvent::MemberUpdate, e).await;
+}
+
+pub async fn log_role_create(ctx: &Context, guild_id: GuildId, role: &Role) {
+    let mut e = CreateEmbed::default();
+    e.colour(MAIN_COLOR)
+        .description(format!("**Role created: <@&{}>**", role.id.0))
+        .footer(|f| f.text(format!("Role ID: {}", role.id.0)));
+
+    send_log(ctx, guild_id, LogEvent::Roles, e).await;
+}
+
+pub async fn log_role_delete(
+    ctx: &Context,
+    guild_id: GuildId,
+    role_id: RoleId,
+    role: Option<Role>,
+) {
+    let name = match role {
+        Some(role) => role.name,
+        None => role_id.0.to_string(),
+    };
+
+    let mut e = CreateEmbed::default();
+    e.colour(ERROR_COLOR)
+        .description(format!("**Role deleted: {}**", name))
+        .footer(|f| f.text(format!("Role ID: {}", role_id.0)));
+
+    send_log(ctx, guild_id, LogEvent::Roles, e).await;
+}
+
+pub async fn log_role_update(ctx: &Context, guild_id: GuildId, old: Option<Role>, new: &Role) {
+    let old = match old {
+        Some(old) => old,
+        None => return,
+    };
+
+    // Position changes are left out on purpose, as moving one role updates all roles below it
+    let mut changes = vec![];
+
+    if old.name != new.name {
+        changes.push(("Name", format!("{} → {}", old.name, new.name)));
+    }
+    if old.colour.0 != new.colour.0 {
+        changes.push((
+            "Colour",
+            format!("#{:06x} → #{:06x}", old.colour.0, new.colour.0),
+        ));
+    }
+    if old.permissions != new.permissions {
+        changes.push((
+            "Permissions",
+            format!(
+                "**Added**: {}\n**Removed**: {}",
+                or_none((new.permissions - old.permissions).to_string()),
+                or_none((old.permissions - new.permissions).to_string())
+            ),
+        ));
+    }
+    if old.hoist != new.hoist {
+        changes.push((
+            "Displayed separately",
+            format!("{} → {}", old.hoist, new.hoist),
+        ));
+    }
+    if old.mentionable != new.mentionable {
+        changes.push((
+            "Mentionable",
+            format!("{} → {}", old.mentionable, new.mentionable),
+        ));
+    }
+
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut e = CreateEmbed::default();
+    e.colour(MAIN_COLOR)
+        .description(format!("**Role updated: <@&{}>**", new.id.0))
+        .fields(
+            changes
+                .into_iter()
+                .map(|(name, value)| (name, value, false)),
+        )
+        .footer(|f| f.text(format!("Role ID: {}", new.id.0)));
+
+    send_log(ctx, guild_id, LogEvent::Roles, e).await;
+}
+
+pub async fn log_channel_create(ctx: &Context, channel: &GuildChannel) {
+    let mut e = CreateEmbed::default();
+    e.colour(MAIN_COLOR)
+        .description(format!(
+            "**Channel created: <#{}>** ({})",
+            channel.id.0,
+            channel.kind.name()
+        ))
+        .footer(|f| f.text(format!("Channel ID: {}", channel.id.0)));
+
+    send_log(ctx, channel.guild_id, LogEvent::Channels, e).await;
+}
+
+pub async fn log_channel_delete(ctx: &Context, channel: &GuildChannel) {
+    let mut e = CreateEmbed::default();
+    e.colour(ERROR_COLOR)
+        .description(format!(
+            "**Channel deleted: #{}** ({})",
+            channel.name,
+            channel.kind.name()
+        ))
+        .footer(|f| f.text(format!("Channel ID: {}", channel.id.0)));
+
+    send_log(ctx, channel.guild_id, LogEvent::Channels, e).await;
+}
+
+pub async fn log_channel_update(ctx: &Context, old: Option<Channel>, new: Channel) {
+    let (old, new) = match (old.and_then(|c| c.guild()), new.guild()) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return,
+    };
+
+    let mut changes = vec![];
+
+    if old.name != new.name {
+        changes.push(("Name", format!("{} → {}", old.name, new.name)));
+    }
+    if old.topic != new.topic {
+        changes.push((
+            "Old topic",
+            or_none(truncate(old.topic.as_deref().unwrap_or_default())),
+        ));
+        changes.push((
+            "New topic",
+            or_none(truncate(new.topic.as_deref().unwrap_or_default())),
+        ));
+    }
+    if old.nsfw != new.nsfw {
+        changes.push(("NSFW", format!("{} → {}", old.nsfw, new.nsfw)));
+    }
+    if old.slow_mode_rate != new.slow_mode_rate {
+        changes.push((
+            "Slowmode",
+            format!(
+                "{}s → {}s",
+                old.slow_mode_rate.unwrap_or_default(),
+                new.slow_mode_rate.unwrap_or_default()
+            ),
+        ));
+    }
+
+    let overwrites = |channel: &GuildChannel| {
+        channel
+            .permission_overwrites
+            .iter()
+            .map(|o| (format!("{:?}", o.kind), o.allow.bits, o.deny.bits))
+            .collect::<Vec<(String, u64, u64)>>()
+    };
+    if overwrites(&old) != overwrites(&new) {
+        changes.push(("Permissions", "Permission overwrites changed".to_string()));
+    }
+
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut e = CreateEmbed::default();
+    e.colour(MAIN_COLOR)
+        .description(format!("**Channel updated: <#{}>**", new.id.0))
+        .fields(
+            changes
+                .into_iter()
+                .map(|(name, value)| (name, value, false)),
+        )
+        .footer(|f| f.text(format!("Channel ID: {}", new.id.0)));
+
+    send_log(ctx, new.guild_id, LogEvent::Channels, e).await;
+}
+
+/// Posts the embed to the guild's log channel for the event, if one is set
+async fn send_log(ctx: &Context, guild_id: GuildId, event: LogEvent, mut embed: CreateEmbed) {
+    let channel_id = {
+        let store = get_store::<LoggingData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0).and_then(|g| g.get(&event)) {
+            Some(channel_id) => ChannelId(*channel_id),
+            None => return,
+        }
+    };
+
+    embed.timestamp(&Utc::now());
+
+    let _ = channel_id
+        .send_message(&ctx.http, |m| m.set_embed(embed))
+        .await;
+}
+
+fn create_user_embed(user: &User) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+    e.author(|a| a.name(user.tag()).icon_url(user.face()))
+        .footer(|f| f.text(format!("User ID: {}", user.id.0)));
+    e
+}
+
+fn format_roles(roles: &[RoleId]) -> String {
+    roles
+        .iter()
+        .map(|r| format!("<@&{}>", r.0))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_LOGGED_CONTENT_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+fn or_empty(text: String) -> String {
+    match text.is_empty() {
+        true => "*empty*".to_string(),
+        false => text,
+    }
+}
+
+fn or_none(text: String) -> String {
+    match text.is_empty() {
+        true => "*none*".to_string(),
+        false => text,
+    }
+}
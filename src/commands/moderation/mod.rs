@@ -1,15 +1,60 @@
 use crate::core::checks::MODERATOR_CHECK;
 use serenity::framework::standard::macros::group;
 
+pub mod antiraid;
+mod archive;
+pub mod automod;
+pub mod autorole;
 mod boosts;
+pub mod cases;
 mod fetch;
+pub mod logging;
+mod punishments;
+mod purge;
+pub mod rolemenu;
 mod serverlist;
+pub mod starboard;
+mod warnings;
+pub mod welcome;
 
+use self::antiraid::{ANTIRAID_COMMAND, LOCKDOWN_COMMAND};
+use self::archive::ARCHIVE_COMMAND;
+use self::automod::AUTOMOD_COMMAND;
+use self::autorole::{AUTOROLE_COMMAND, GATE_COMMAND};
 use self::boosts::BOOSTS_COMMAND;
+use self::cases::{CASE_COMMAND, MODLOG_COMMAND, REASON_COMMAND};
 use self::fetch::FETCH_COMMAND;
+use self::logging::LOGGING_COMMAND;
+use self::punishments::{
+    BAN_COMMAND, KICK_COMMAND, SOFTBAN_COMMAND, TEMPBAN_COMMAND, TEMPMUTE_COMMAND,
+    TEMPROLE_COMMAND, TIMEOUT_COMMAND, UNBAN_COMMAND,
+};
+use self::purge::PURGE_COMMAND;
+use self::rolemenu::ROLEMENU_COMMAND;
 use self::serverlist::SERVERLIST_COMMAND;
+use self::starboard::STARBOARD_COMMAND;
+use self::warnings::{
+    CLEARWARNS_COMMAND, DELWARN_COMMAND, WARNINGS_COMMAND, WARNRULES_COMMAND, WARN_COMMAND,
+};
+use self::welcome::{GOODBYE_COMMAND, WELCOME_COMMAND};
+
+pub use self::antiraid::AntiraidData;
+pub use self::automod::AutomodData;
+pub use self::autorole::AutoroleData;
+pub use self::cases::CaseData;
+pub use self::logging::LoggingData;
+pub use self::punishments::{RemoveRoleExecutor, UnbanExecutor};
+pub use self::rolemenu::RoleMenuData;
+pub use self::starboard::StarboardData;
+pub use self::warnings::WarningData;
+pub use self::welcome::WelcomeData;
 
 #[group]
+#[only_in(guilds)]
 #[checks(Moderator)]
-#[commands(fetch, boosts, serverlist)]
+#[commands(
+    fetch, boosts, serverlist, kick, ban, softban, tempban, unban, timeout, tempmute, temprole,
+    warn, warnings, delwarn, clearwarns, warnrules, modlog, case, reason, purge, archive, automod,
+    antiraid, lockdown, logging, welcome, goodbye, autorole, gate, rolemenu, starboard
+)]
 struct Moderation;
@@ -0,0 +1,557 @@
+use super::cases::{create_case, CaseAction};
+use crate::core::{
+    constants::MAIN_COLOR,
+    moderation::{
+        check_hierarchy, check_role_position, notify_user, timeout_member, DEFAULT_REASON,
+    },
+    scheduler::{cancel_all, schedule, ActionExecutor, ScheduledAction},
+    util::{format_duration, parse_duration},
+};
+use chrono::{Duration, Utc};
+use serenity::{
+    async_trait,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        id::{GuildId, RoleId, UserId},
+        user::User,
+    },
+    prelude::{Context, SerenityError},
+};
+
+// Discord doesn't allow timeouts longer than 28 days
+const MAX_TIMEOUT_DAYS: i64 = 28;
+const MUTED_ROLE_NAME: &str = "muted";
+
+#[command]
+#[description("Kicks a member from the server and notifies them via DM")]
+#[usage("<user> [reason]")]
+#[example("@user Spamming in #general")]
+#[min_args(1)]
+pub async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let reason = parse_reason(&args);
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    notify_user(ctx, guild_id, user.id, "kicked", &reason).await;
+    guild_id
+        .kick_with_reason(&ctx.http, user.id, &reason)
+        .await?;
+
+    send_action_msg(ctx, msg, "Kicked", &user, &reason, None).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Kick,
+        user.id,
+        msg.author.id,
+        &reason,
+        None,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description(
+    "Bans a user from the server and notifies them via DM. \
+    Optionally deletes their messages of the last 0-7 days."
+)]
+#[usage("<user> [delete message days] [reason]")]
+#[example("@user Posting scam links")]
+#[example("@user 7 Raiding")]
+#[min_args(1)]
+pub async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let delete_days = parse_delete_days(&mut args, 0);
+    let reason = parse_reason(&args);
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    notify_user(ctx, guild_id, user.id, "banned", &reason).await;
+    guild_id
+        .ban_with_reason(&ctx.http, user.id, delete_days, &reason)
+        .await?;
+    // A permanent ban mustn't be lifted when an earlier temporary ban expires
+    cancel_pending_unban(ctx, guild_id, user.id).await;
+
+    send_action_msg(ctx, msg, "Banned", &user, &reason, None).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Ban,
+        user.id,
+        msg.author.id,
+        &reason,
+        None,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description(
+    "Bans and immediately unbans a member to kick them while deleting their messages \
+    of the last 0-7 days (defaults to 7)."
+)]
+#[usage("<user> [delete message days] [reason]")]
+#[example("@user Spamming")]
+#[min_args(1)]
+pub async fn softban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let delete_days = parse_delete_days(&mut args, 7);
+    let reason = parse_reason(&args);
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    notify_user(ctx, guild_id, user.id, "kicked", &reason).await;
+    guild_id
+        .ban_with_reason(&ctx.http, user.id, delete_days, &reason)
+        .await?;
+    guild_id.unban(&ctx.http, user.id).await?;
+    cancel_pending_unban(ctx, guild_id, user.id).await;
+
+    send_action_msg(ctx, msg, "Softbanned", &user, &reason, None).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Softban,
+        user.id,
+        msg.author.id,
+        &reason,
+        None,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description(
+    "Bans a user for the given duration and notifies them via DM. \
+    Durations are written like 30m, 12h, 7d or 1w2d."
+)]
+#[usage("<user> <duration> [reason]")]
+#[example("@user 7d Repeated insults")]
+#[min_args(2)]
+pub async fn tempban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let duration = parse_duration_arg(&mut args)?;
+    let reason = parse_reason(&args);
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    notify_user(
+        ctx,
+        guild_id,
+        user.id,
+        &format!("banned for {}", format_duration(duration)),
+        &reason,
+    )
+    .await;
+    guild_id
+        .ban_with_reason(&ctx.http, user.id, 0, &reason)
+        .await?;
+
+    // Lift the ban once the duration is over, replacing the expiry of an earlier temporary ban
+    cancel_pending_unban(ctx, guild_id, user.id).await;
+    schedule(
+        ctx,
+        Utc::now() + duration,
+        ScheduledAction::Unban {
+            guild_id: guild_id.0,
+            user_id: user.id.0,
+        },
+    )
+    .await;
+
+    send_action_msg(
+        ctx,
+        msg,
+        "Temporarily banned",
+        &user,
+        &reason,
+        Some(duration),
+    )
+    .await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Tempban,
+        user.id,
+        msg.author.id,
+        &reason,
+        Some(duration),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Lifts the ban of a user")]
+#[usage("<user id> [reason]")]
+#[example("134040353517862912 Appeal accepted")]
+#[min_args(1)]
+pub async fn unban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let reason = parse_reason(&args);
+
+    guild_id
+        .unban(&ctx.http, user.id)
+        .await
+        .map_err(|_| CommandError::from("That user isn't banned"))?;
+    // Otherwise the expiry of a temporary ban would lift a later ban
+    cancel_pending_unban(ctx, guild_id, user.id).await;
+
+    send_action_msg(ctx, msg, "Unbanned", &user, &reason, None).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Unban,
+        user.id,
+        msg.author.id,
+        &reason,
+        None,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("mute")]
+#[description(
+    "Times out a member for the given duration (up to 28 days), \
+    so they can't write, react or speak. Use `off` as duration to lift a timeout."
+)]
+#[usage("<user> <duration|off> [reason]")]
+#[example("@user 1h Calm down")]
+#[example("@user off")]
+#[min_args(2)]
+pub async fn timeout(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    if args.current() == Some("off") {
+        args.advance();
+        let reason = parse_reason(&args);
+
+        timeout_member(ctx, guild_id, user.id, None).await?;
+        send_action_msg(ctx, msg, "Lifted timeout of", &user, &reason, None).await;
+        create_case(
+            ctx,
+            guild_id,
+            CaseAction::Untimeout,
+            user.id,
+            msg.author.id,
+            &reason,
+            None,
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    let duration = parse_duration_arg(&mut args)?;
+    let reason = parse_reason(&args);
+
+    if duration > Duration::days(MAX_TIMEOUT_DAYS) {
+        return Err(CommandError::from(format!(
+            "Timeouts can't be longer than {} days",
+            MAX_TIMEOUT_DAYS
+        )));
+    }
+
+    notify_user(
+        ctx,
+        guild_id,
+        user.id,
+        &format!("timed out for {}", format_duration(duration)),
+        &reason,
+    )
+    .await;
+    timeout_member(ctx, guild_id, user.id, Some(Utc::now() + duration)).await?;
+
+    send_action_msg(ctx, msg, "Timed out", &user, &reason, Some(duration)).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Timeout,
+        user.id,
+        msg.author.id,
+        &reason,
+        Some(duration),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description(
+    "Gives a member the server's `Muted` role for the given duration. \
+    Unlike timeouts, this isn't limited to 28 days."
+)]
+#[usage("<user> <duration> [reason]")]
+#[example("@user 30d Repeated spam")]
+#[min_args(2)]
+pub async fn tempmute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let duration = parse_duration_arg(&mut args)?;
+    let reason = parse_reason(&args);
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    let muted_role_id = find_role_by_name(ctx, guild_id, MUTED_ROLE_NAME)
+        .await
+        .ok_or_else(|| {
+            CommandError::from("This server needs a role called `Muted` to use temporary mutes")
+        })?;
+
+    notify_user(
+        ctx,
+        guild_id,
+        user.id,
+        &format!("muted for {}", format_duration(duration)),
+        &reason,
+    )
+    .await;
+    add_temporary_role(ctx, guild_id, user.id, muted_role_id, duration).await?;
+
+    send_action_msg(ctx, msg, "Muted", &user, &reason, Some(duration)).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Mute,
+        user.id,
+        msg.author.id,
+        &reason,
+        Some(duration),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Gives a member a role that is removed again after the given duration")]
+#[usage("<user> <role> <duration>")]
+#[example("@user @Event 3d")]
+#[min_args(3)]
+pub async fn temprole(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user = parse_user_arg(ctx, &mut args).await?;
+    let role_id = args
+        .single::<RoleId>()
+        .map_err(|_| CommandError::from("Please supply a valid role mention or id"))?;
+    let duration = parse_duration_arg(&mut args)?;
+
+    check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+    check_role_position(ctx, guild_id, msg.author.id, role_id).await?;
+
+    add_temporary_role(ctx, guild_id, user.id, role_id, duration).await?;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Gave <@&{}> to <@{}> for **{}**",
+                    role_id.0,
+                    user.id.0,
+                    format_duration(duration)
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Removes the scheduled expiry of a temporary ban
+async fn cancel_pending_unban(ctx: &Context, guild_id: GuildId, user_id: UserId) {
+    cancel_all(ctx, |task| {
+        matches!(
+            task.action,
+            ScheduledAction::Unban { guild_id: g, user_id: u } if g == guild_id.0 && u == user_id.0
+        )
+    })
+    .await;
+}
+
+/// Adds a role to a member and schedules its removal.
+/// An earlier scheduled removal of the same role is replaced, so it can't end the new one early.
+async fn add_temporary_role(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+    duration: Duration,
+) -> CommandResult {
+    ctx.http
+        .add_member_role(guild_id.0, user_id.0, role_id.0)
+        .await
+        .map_err(|_| CommandError::from("I couldn't give that role to the member"))?;
+
+    cancel_all(ctx, |task| {
+        matches!(
+            task.action,
+            ScheduledAction::RemoveRole { guild_id: g, user_id: u, role_id: r }
+                if g == guild_id.0 && u == user_id.0 && r == role_id.0
+        )
+    })
+    .await;
+    schedule(
+        ctx,
+        Utc::now() + duration,
+        ScheduledAction::RemoveRole {
+            guild_id: guild_id.0,
+            user_id: user_id.0,
+            role_id: role_id.0,
+        },
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Lifts temporary bans once they expire
+pub struct UnbanExecutor;
+
+#[async_trait]
+impl ActionExecutor for UnbanExecutor {
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError> {
+        let (guild_id, user_id) = match action {
+            ScheduledAction::Unban { guild_id, user_id } => (GuildId(*guild_id), UserId(*user_id)),
+            _ => return Ok(()),
+        };
+
+        guild_id.unban(&ctx.http, user_id).await?;
+
+        let bot_id = ctx.cache.current_user_id().await;
+        create_case(
+            ctx,
+            guild_id,
+            CaseAction::Unban,
+            user_id,
+            bot_id,
+            "Temporary ban expired",
+            None,
+        )
+        .await;
+
+        Ok(())
+    }
+}
+
+/// Removes temporary roles once they expire
+pub struct RemoveRoleExecutor;
+
+#[async_trait]
+impl ActionExecutor for RemoveRoleExecutor {
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError> {
+        if let ScheduledAction::RemoveRole {
+            guild_id,
+            user_id,
+            role_id,
+        } = action
+        {
+            ctx.http
+                .remove_member_role(*guild_id, *user_id, *role_id)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn find_role_by_name(ctx: &Context, guild_id: GuildId, name: &str) -> Option<RoleId> {
+    ctx.cache
+        .guild_field(guild_id, |g| {
+            g.roles
+                .values()
+                .find(|r| r.name.eq_ignore_ascii_case(name))
+                .map(|r| r.id)
+        })
+        .await
+        .flatten()
+}
+
+async fn parse_user_arg(ctx: &Context, args: &mut Args) -> Result<User, CommandError> {
+    let user_id = args
+        .single::<UserId>()
+        .map_err(|_| CommandError::from("Please supply a valid user mention or id"))?;
+
+    user_id
+        .to_user(ctx)
+        .await
+        .map_err(|_| CommandError::from("Couldn't find that user"))
+}
+
+fn parse_duration_arg(args: &mut Args) -> Result<Duration, CommandError> {
+    args.single::<String>()
+        .ok()
+        .and_then(|d| parse_duration(&d))
+        .ok_or_else(|| {
+            CommandError::from(
+                "Please supply a valid duration of up to 5 years, i.e. 30m, 12h, 7d or 1w2d",
+            )
+        })
+}
+
+/// Reads the optional amount of days of messages to delete on a ban.
+/// If the argument isn't a valid number of days, it's treated as part of the reason.
+fn parse_delete_days(args: &mut Args, default: u8) -> u8 {
+    match args.parse::<u8>() {
+        Ok(days) if days <= 7 => {
+            args.advance();
+            days
+        }
+        _ => default,
+    }
+}
+
+fn parse_reason(args: &Args) -> String {
+    match args.rest() {
+        "" => DEFAULT_REASON.to_string(),
+        reason => reason.to_string(),
+    }
+}
+
+async fn send_action_msg(
+    ctx: &Context,
+    msg: &Message,
+    action: &str,
+    user: &User,
+    reason: &str,
+    duration: Option<Duration>,
+) {
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("{} {}", action, user.tag()))
+                    .field("Reason", reason, false);
+
+                if let Some(duration) = duration {
+                    e.field("Duration", format_duration(duration), false);
+                }
+
+                e
+            })
+        })
+        .await;
+}
@@ -0,0 +1,263 @@
+use crate::core::constants::MAIN_COLOR;
+use chrono::{Duration, Utc};
+use log::debug;
+use regex::Regex;
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        id::{MessageId, UserId},
+    },
+    prelude::Context,
+};
+
+const REQUESTS_PER_ITER: u64 = 100;
+const MAX_PURGE_AMOUNT: usize = 1000;
+const MAX_SCANNED_MESSAGES: usize = 5000;
+// Discord only allows bulk deleting messages younger than 14 days
+const BULK_DELETE_MAX_AGE_DAYS: i64 = 14;
+// Message IDs are snowflakes, so anything below that can safely be treated as an amount
+const MIN_MESSAGE_ID: u64 = 1 << 32;
+
+#[derive(Default)]
+struct PurgeFilter {
+    author: Option<UserId>,
+    bots_only: bool,
+    contains: Option<String>,
+    attachments_only: bool,
+    regex: Option<Regex>,
+}
+
+impl PurgeFilter {
+    fn matches(&self, message: &Message) -> bool {
+        if let Some(author) = self.author {
+            if message.author.id != author {
+                return false;
+            }
+        }
+
+        if self.bots_only && !message.author.bot {
+            return false;
+        }
+
+        if let Some(text) = &self.contains {
+            if !message.content.to_lowercase().contains(text) {
+                return false;
+            }
+        }
+
+        if self.attachments_only && message.attachments.is_empty() {
+            return false;
+        }
+
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(&message.content) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+enum PurgeRange {
+    Last(usize),
+    Between(MessageId, MessageId),
+}
+
+#[command]
+#[aliases("prune", "clear")]
+#[description(
+    "Deletes the last given amount of messages, or all messages between two message IDs. \
+    The messages can be narrowed down with the following filters: \n\
+    - `user <user>`: only messages by that user \n\
+    - `bots`: only messages by bots \n\
+    - `contains <text>`: only messages containing that text (use quotes for multiple words) \n\
+    - `attachments`: only messages with attachments \n\
+    - `regex <pattern>`: only messages matching that regular expression"
+)]
+#[usage("<amount> [filters]")]
+#[usage("<newest message id> <oldest message id> [filters]")]
+#[example("50")]
+#[example("100 user @user")]
+#[example("200 bots contains \"level up\"")]
+#[example("725681148134424596 725681148134424582 attachments")]
+#[min_args(1)]
+pub async fn purge(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let range = parse_range(&mut args)?;
+    let filter = parse_filter(&mut args)?;
+
+    let _ = msg.delete(&ctx).await;
+
+    // Search starts either at the command's message or right at the newest message of the range,
+    // ending once enough messages matched or the oldest message of the range has been reached
+    let (mut last_message_id, amount, end_id) = match range {
+        PurgeRange::Last(amount) => (msg.id, amount, MessageId(0)),
+        PurgeRange::Between(from, to) => (MessageId(from.0 + 1), MAX_PURGE_AMOUNT, to),
+    };
+
+    let mut matching_messages: Vec<MessageId> = vec![];
+    let mut message_processed_counter: usize = 0;
+    let mut end_reached = false;
+
+    while !end_reached {
+        // Show typing status
+        let _ = msg.channel_id.broadcast_typing(&ctx.http).await;
+
+        let messages: Vec<Message> = msg
+            .channel_id
+            .messages(&ctx.http, |retriever| {
+                retriever.before(last_message_id).limit(REQUESTS_PER_ITER)
+            })
+            .await?;
+
+        debug!("Requested {} new messages from discord", &REQUESTS_PER_ITER);
+
+        if messages.len() < REQUESTS_PER_ITER as usize {
+            end_reached = true;
+        }
+
+        for message in messages {
+            if message.id < end_id
+                || matching_messages.len() >= amount
+                || message_processed_counter >= MAX_SCANNED_MESSAGES
+            {
+                end_reached = true;
+                break;
+            }
+
+            if filter.matches(&message) {
+                matching_messages.push(message.id);
+            }
+
+            last_message_id = message.id;
+            message_processed_counter += 1;
+        }
+    }
+
+    let deleted_count = delete_messages(ctx, msg, matching_messages).await?;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Deleted **{}** messages out of **{}** processed messages",
+                    deleted_count, message_processed_counter
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+fn parse_range(args: &mut Args) -> Result<PurgeRange, CommandError> {
+    let first = args
+        .single::<u64>()
+        .map_err(|_| CommandError::from("Please supply an amount of messages or a message id"))?;
+
+    if first < MIN_MESSAGE_ID {
+        return match first as usize {
+            amount if amount > 0 && amount <= MAX_PURGE_AMOUNT => Ok(PurgeRange::Last(amount)),
+            _ => Err(CommandError::from(format!(
+                "The amount must be between 1 and {}",
+                MAX_PURGE_AMOUNT
+            ))),
+        };
+    }
+
+    let second = args
+        .single::<u64>()
+        .ok()
+        .filter(|id| *id >= MIN_MESSAGE_ID)
+        .ok_or_else(|| CommandError::from("Please supply a second message id to end at"))?;
+
+    // Accept the message ids in any order
+    Ok(PurgeRange::Between(
+        MessageId(first.max(second)),
+        MessageId(first.min(second)),
+    ))
+}
+
+fn parse_filter(args: &mut Args) -> Result<PurgeFilter, CommandError> {
+    let mut filter = PurgeFilter::default();
+
+    while !args.is_empty() {
+        let name = args.single::<String>()?.to_lowercase();
+
+        match name.as_str() {
+            "user" => {
+                filter.author = Some(args.single::<UserId>().map_err(|_| {
+                    CommandError::from("The user filter needs a valid user mention or id")
+                })?);
+            }
+            "bots" => filter.bots_only = true,
+            "contains" => {
+                let text = args
+                    .quoted()
+                    .single::<String>()
+                    .map_err(|_| CommandError::from("The contains filter needs a text"))?;
+                filter.contains = Some(text.to_lowercase());
+            }
+            "attachments" => filter.attachments_only = true,
+            "regex" => {
+                let pattern = args
+                    .quoted()
+                    .single::<String>()
+                    .map_err(|_| CommandError::from("The regex filter needs a pattern"))?;
+                filter.regex = Some(
+                    Regex::new(&pattern)
+                        .map_err(|_| CommandError::from("The regex pattern is invalid"))?,
+                );
+            }
+            _ => {
+                return Err(CommandError::from(format!(
+                    "Unknown filter `{}`. See `~help purge` for the available filters",
+                    name
+                )))
+            }
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Deletes the given messages, using bulk deletes wherever possible.
+/// Returns the amount of deleted messages.
+async fn delete_messages(
+    ctx: &Context,
+    msg: &Message,
+    message_ids: Vec<MessageId>,
+) -> Result<usize, CommandError> {
+    let bulk_delete_threshold =
+        Utc::now() - Duration::days(BULK_DELETE_MAX_AGE_DAYS) + Duration::minutes(1);
+
+    let (recent_ids, old_ids): (Vec<MessageId>, Vec<MessageId>) = message_ids
+        .into_iter()
+        .partition(|id| id.created_at() > bulk_delete_threshold);
+
+    let mut deleted_count = 0;
+
+    // Bulk deletes take 2 to 100 messages at once
+    for chunk in recent_ids.chunks(100) {
+        match chunk {
+            [single_id] => msg.channel_id.delete_message(&ctx.http, single_id).await?,
+            ids => msg.channel_id.delete_messages(&ctx.http, ids).await?,
+        }
+        deleted_count += chunk.len();
+    }
+
+    for id in old_ids {
+        // Show typing status, as deleting old messages one by one takes a while
+        if deleted_count % 10 == 0 {
+            let _ = msg.channel_id.broadcast_typing(&ctx.http).await;
+        }
+
+        if msg.channel_id.delete_message(&ctx.http, id).await.is_ok() {
+            deleted_count += 1;
+        }
+    }
+
+    Ok(deleted_count)
+}
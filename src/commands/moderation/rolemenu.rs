@@ -0,0 +1,614 @@
+use crate::core::{
+    constants::MAIN_COLOR,
+    moderation::{check_role_position, check_unprivileged_role},
+    storage::get_store,
+    util::{is_guild_channel, parse_emoji, reaction_matches},
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::{Message, Reaction, ReactionType},
+        id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+        interactions::{
+            Interaction, InteractionApplicationCommandCallbackDataFlags, InteractionData,
+            InteractionResponseType,
+        },
+    },
+    prelude::Context,
+};
+use std::{collections::HashMap, str::FromStr};
+
+// Discord allows 20 different reactions per message
+const MAX_MENU_ENTRIES: usize = 20;
+const SELECT_MENU_ID: &str = "rolemenu";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RoleMenuData {
+    // Menus by their message id
+    menus: HashMap<u64, RoleMenu>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RoleMenu {
+    guild_id: u64,
+    channel_id: u64,
+    title: String,
+    mode: RoleMenuMode,
+    entries: Vec<RoleMenuEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RoleMenuEntry {
+    emoji: String,
+    role_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum RoleMenuMode {
+    Toggle,
+    Unique,
+    Verify,
+}
+
+impl RoleMenuMode {
+    fn parse(name: &str) -> Option<RoleMenuMode> {
+        match name.to_lowercase().as_str() {
+            "toggle" => Some(RoleMenuMode::Toggle),
+            "unique" => Some(RoleMenuMode::Unique),
+            "verify" => Some(RoleMenuMode::Verify),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            RoleMenuMode::Toggle => "toggle",
+            RoleMenuMode::Unique => "unique",
+            RoleMenuMode::Verify => "verify",
+        }
+    }
+
+    fn describe(&self) -> &str {
+        match self {
+            RoleMenuMode::Toggle => {
+                "React or pick a role to get it, remove your reaction or pick it again to lose it."
+            }
+            RoleMenuMode::Unique => {
+                "React or pick a role to get it. You can only have one of these roles."
+            }
+            RoleMenuMode::Verify => {
+                "React or pick a role to get it. Roles stay when removing your reaction."
+            }
+        }
+    }
+}
+
+impl RoleMenu {
+    fn find_entry(&self, emoji: &ReactionType) -> Option<&RoleMenuEntry> {
+        self.entries.iter().find(|entry| {
+            ReactionType::from_str(&entry.emoji).is_ok_and(|e| reaction_matches(&e, emoji))
+        })
+    }
+}
+
+#[command]
+#[sub_commands(rolemenu_create, rolemenu_add, rolemenu_remove, rolemenu_delete)]
+#[description(
+    "Lists the role menus of this server. Role menus are messages that members can react to, \
+    or pick roles from their select menu, to give themselves roles. Menus have one of the \
+    following modes: \n\
+    - `toggle`: removing the reaction or picking the role again removes it \n\
+    - `unique`: members can only have one role of the menu at once \n\
+    - `verify`: roles can only be gained, removing a reaction keeps the role"
+)]
+pub async fn rolemenu(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let description = {
+        let store = get_store::<RoleMenuData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .menus
+            .iter()
+            .filter(|(_, menu)| menu.guild_id == guild_id.0)
+            .map(|(message_id, menu)| {
+                format!(
+                    "[{}](https://discord.com/channels/{}/{}/{}) ({} roles, {}) - ID: {}",
+                    menu.title,
+                    menu.guild_id,
+                    menu.channel_id,
+                    message_id,
+                    menu.entries.len(),
+                    menu.mode.name(),
+                    message_id
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Role menus")
+                    .description(match description.is_empty() {
+                        true => "There are no role menus yet. Use `rolemenu create` to create one."
+                            .to_string(),
+                        false => description,
+                    })
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("create")]
+#[description("Posts a new, empty role menu to the channel. Add roles with `rolemenu add`.")]
+#[usage("<#channel> <toggle|unique|verify> <title>")]
+#[example("#roles unique Pick your color")]
+#[example("#roles toggle Notifications")]
+#[min_args(3)]
+async fn rolemenu_create(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let channel_id = args
+        .single::<ChannelId>()
+        .map_err(|_| CommandError::from("Please supply a valid channel mention or id"))?;
+    if !is_guild_channel(ctx, guild_id, channel_id).await {
+        return Err(CommandError::from("Please supply a channel of this server"));
+    }
+    let mode = RoleMenuMode::parse(&args.single::<String>()?).ok_or_else(|| {
+        CommandError::from("The mode must be one of `toggle`, `unique` or `verify`")
+    })?;
+
+    let menu = RoleMenu {
+        guild_id: guild_id.0,
+        channel_id: channel_id.0,
+        title: args.rest().to_string(),
+        mode,
+        entries: vec![],
+    };
+
+    let menu_message = channel_id
+        .send_message(&ctx.http, |m| m.set_embed(create_menu_embed(&menu)))
+        .await?;
+
+    {
+        let store = get_store::<RoleMenuData>(ctx).await;
+        let mut store = store.write().await;
+        store.menus.insert(menu_message.id.0, menu);
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Created the role menu in <#{}>. Add roles with `rolemenu add {} <emoji> <role>`",
+                    channel_id.0, menu_message.id.0
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("add")]
+#[description("Adds a role to a role menu")]
+#[usage("<menu message id> <emoji> <role>")]
+#[example("725681148134424596 🔴 @Red")]
+#[min_args(3)]
+async fn rolemenu_add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let message_id = parse_menu_id(&mut args)?;
+    let emoji = args
+        .single::<String>()
+        .ok()
+        .and_then(|e| parse_emoji(&e))
+        .ok_or_else(|| CommandError::from("Please supply a valid emoji"))?;
+    let role_id = args
+        .single::<RoleId>()
+        .map_err(|_| CommandError::from("Please supply a valid role mention or id"))?;
+
+    // Members pick menu roles themselves, so they must be below the moderator and grant nothing
+    let role = check_role_position(ctx, guild_id, msg.author.id, role_id).await?;
+    check_unprivileged_role(&role)?;
+
+    let menu = {
+        let store = get_store::<RoleMenuData>(ctx).await;
+        let mut store = store.write().await;
+        let menu = get_menu_mut(&mut store.menus, message_id, guild_id.0)?;
+
+        if menu.entries.len() >= MAX_MENU_ENTRIES {
+            return Err(CommandError::from(format!(
+                "A role menu can't have more than {} roles",
+                MAX_MENU_ENTRIES
+            )));
+        }
+        if menu.find_entry(&emoji).is_some() {
+            return Err(CommandError::from("That emoji is already used in the menu"));
+        }
+        if menu.entries.iter().any(|e| e.role_id == role_id.0) {
+            return Err(CommandError::from("That role is already part of the menu"));
+        }
+
+        menu.entries.push(RoleMenuEntry {
+            emoji: emoji.to_string(),
+            role_id: role_id.0,
+        });
+        let menu = menu.clone();
+
+        store.save();
+        menu
+    };
+
+    let channel_id = ChannelId(menu.channel_id);
+    channel_id
+        .create_reaction(&ctx.http, message_id, emoji)
+        .await
+        .map_err(|_| CommandError::from("I can't react with that emoji"))?;
+    update_menu_message(ctx, message_id, &menu).await?;
+
+    send_confirmation(ctx, msg, format!("Added <@&{}> to the menu", role_id.0)).await;
+    Ok(())
+}
+
+#[command("remove")]
+#[description("Removes a role from a role menu")]
+#[usage("<menu message id> <role>")]
+#[example("725681148134424596 @Red")]
+#[min_args(2)]
+async fn rolemenu_remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let message_id = parse_menu_id(&mut args)?;
+    let role_id = args
+        .single::<RoleId>()
+        .map_err(|_| CommandError::from("Please supply a valid role mention or id"))?;
+
+    let (menu, removed_entry) = {
+        let store = get_store::<RoleMenuData>(ctx).await;
+        let mut store = store.write().await;
+        let menu = get_menu_mut(&mut store.menus, message_id, guild_id.0)?;
+
+        let index = menu
+            .entries
+            .iter()
+            .position(|e| e.role_id == role_id.0)
+            .ok_or_else(|| CommandError::from("That role isn't part of the menu"))?;
+        let removed_entry = menu.entries.remove(index);
+        let menu = menu.clone();
+
+        store.save();
+        (menu, removed_entry)
+    };
+
+    if let Ok(emoji) = ReactionType::from_str(&removed_entry.emoji) {
+        let _ = ctx
+            .http
+            .delete_message_reaction_emoji(menu.channel_id, message_id.0, &emoji)
+            .await;
+    }
+    update_menu_message(ctx, message_id, &menu).await?;
+
+    send_confirmation(ctx, msg, format!("Removed <@&{}> from the menu", role_id.0)).await;
+    Ok(())
+}
+
+#[command("delete")]
+#[description("Deletes a role menu and its message")]
+#[usage("<menu message id>")]
+#[example("725681148134424596")]
+#[min_args(1)]
+async fn rolemenu_delete(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let message_id = parse_menu_id(&mut args)?;
+
+    let menu = {
+        let store = get_store::<RoleMenuData>(ctx).await;
+        let mut store = store.write().await;
+        get_menu_mut(&mut store.menus, message_id, guild_id.0)?;
+
+        let menu = store.menus.remove(&message_id.0);
+        store.save();
+        menu
+    };
+
+    if let Some(menu) = menu {
+        let _ = ChannelId(menu.channel_id)
+            .delete_message(&ctx.http, message_id)
+            .await;
+    }
+
+    send_confirmation(ctx, msg, "Deleted the role menu".to_string()).await;
+    Ok(())
+}
+
+/// Gives members the role of the emoji they reacted with on a role menu
+pub async fn handle_reaction_add(ctx: &Context, reaction: &Reaction) {
+    let user_id = match reaction.user_id {
+        Some(user_id) if user_id != ctx.cache.current_user_id().await => user_id,
+        _ => return,
+    };
+
+    let menu = match get_menu(ctx, reaction.message_id).await {
+        Some(menu) if reaction.guild_id == Some(GuildId(menu.guild_id)) => menu,
+        _ => return,
+    };
+
+    if let Some(entry) = menu.find_entry(&reaction.emoji) {
+        grant_menu_role(ctx, &menu, reaction.message_id, user_id, entry.role_id).await;
+    }
+}
+
+/// Takes away the role of a role menu when members remove their reaction, unless the menu is verify-only
+pub async fn handle_reaction_remove(ctx: &Context, reaction: &Reaction) {
+    let user_id = match reaction.user_id {
+        Some(user_id) if user_id != ctx.cache.current_user_id().await => user_id,
+        _ => return,
+    };
+
+    let menu = match get_menu(ctx, reaction.message_id).await {
+        Some(menu)
+            if menu.mode != RoleMenuMode::Verify
+                && reaction.guild_id == Some(GuildId(menu.guild_id)) =>
+        {
+            menu
+        }
+        _ => return,
+    };
+
+    if let Some(entry) = menu.find_entry(&reaction.emoji) {
+        if let Err(why) = ctx
+            .http
+            .remove_member_role(menu.guild_id, user_id.0, entry.role_id)
+            .await
+        {
+            error!(
+                "Couldn't remove menu role {} from {}: {:?}",
+                entry.role_id, user_id, why
+            );
+        }
+    }
+}
+
+/// Gives members the roles they picked from the select menu of a role menu. Picking a role of a
+/// toggle menu that the member already has takes it away again.
+pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) {
+    let component = match &interaction.data {
+        Some(InteractionData::MessageComponent(component))
+            if component.custom_id == SELECT_MENU_ID =>
+        {
+            component
+        }
+        _ => return,
+    };
+    let (message_id, member) = match (&interaction.message, &interaction.member) {
+        (Some(message), Some(member)) => (message.id(), member),
+        _ => return,
+    };
+    let menu = match get_menu(ctx, message_id).await {
+        Some(menu) if interaction.guild_id == Some(GuildId(menu.guild_id)) => menu,
+        _ => return,
+    };
+
+    let mut changes = vec![];
+    for value in &component.values {
+        let role_id = match value.parse::<u64>() {
+            Ok(role_id) if menu.entries.iter().any(|e| e.role_id == role_id) => role_id,
+            _ => continue,
+        };
+
+        if menu.mode == RoleMenuMode::Toggle && member.roles.contains(&RoleId(role_id)) {
+            match ctx
+                .http
+                .remove_member_role(menu.guild_id, member.user.id.0, role_id)
+                .await
+            {
+                Ok(_) => changes.push(format!("Removed <@&{}>", role_id)),
+                Err(why) => error!(
+                    "Couldn't remove menu role {} from {}: {:?}",
+                    role_id, member.user.id, why
+                ),
+            }
+        } else if grant_menu_role(ctx, &menu, message_id, member.user.id, role_id).await {
+            changes.push(format!("Added <@&{}>", role_id));
+        }
+    }
+
+    let description = match changes.is_empty() {
+        true => "Your roles didn't change".to_string(),
+        false => changes.join("\n"),
+    };
+    let _ = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .create_embed(|e| e.colour(MAIN_COLOR).description(description))
+                })
+        })
+        .await;
+}
+
+/// Forgets role menus whose message was deleted
+pub async fn handle_message_delete(ctx: &Context, message_ids: &[MessageId]) {
+    let store = get_store::<RoleMenuData>(ctx).await;
+    let mut store = store.write().await;
+
+    let count = store.menus.len();
+    store
+        .menus
+        .retain(|id, _| !message_ids.iter().any(|m| m.0 == *id));
+    if store.menus.len() != count {
+        store.save();
+    }
+}
+
+/// Gives a member a role of a menu, returning whether that worked. In unique menus, the other
+/// roles of the menu are taken away, as well as the reactions that granted them.
+async fn grant_menu_role(
+    ctx: &Context,
+    menu: &RoleMenu,
+    message_id: MessageId,
+    user_id: UserId,
+    role_id: u64,
+) -> bool {
+    if menu.mode == RoleMenuMode::Unique {
+        for entry in menu.entries.iter().filter(|e| e.role_id != role_id) {
+            let _ = ctx
+                .http
+                .remove_member_role(menu.guild_id, user_id.0, entry.role_id)
+                .await;
+
+            if let Ok(emoji) = ReactionType::from_str(&entry.emoji) {
+                let _ = ChannelId(menu.channel_id)
+                    .delete_reaction(&ctx.http, message_id, Some(user_id), emoji)
+                    .await;
+            }
+        }
+    }
+
+    if let Err(why) = ctx
+        .http
+        .add_member_role(menu.guild_id, user_id.0, role_id)
+        .await
+    {
+        error!(
+            "Couldn't add menu role {} to {}: {:?}",
+            role_id, user_id, why
+        );
+        return false;
+    }
+
+    true
+}
+
+async fn get_menu(ctx: &Context, message_id: MessageId) -> Option<RoleMenu> {
+    let store = get_store::<RoleMenuData>(ctx).await;
+    let store = store.read().await;
+    store.menus.get(&message_id.0).cloned()
+}
+
+fn get_menu_mut(
+    menus: &mut HashMap<u64, RoleMenu>,
+    message_id: MessageId,
+    guild_id: u64,
+) -> Result<&mut RoleMenu, CommandError> {
+    menus
+        .get_mut(&message_id.0)
+        .filter(|menu| menu.guild_id == guild_id)
+        .ok_or_else(|| CommandError::from("There's no role menu with that message id"))
+}
+
+async fn update_menu_message(
+    ctx: &Context,
+    message_id: MessageId,
+    menu: &RoleMenu,
+) -> CommandResult {
+    let role_names = get_role_names(ctx, menu).await;
+
+    ChannelId(menu.channel_id)
+        .edit_message(&ctx.http, message_id, |m| {
+            m.set_embed(create_menu_embed(menu))
+                .components(|c| add_select_menu(c, menu, role_names))
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn create_menu_embed(menu: &RoleMenu) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    let roles = menu
+        .entries
+        .iter()
+        .map(|entry| format!("{} <@&{}>", entry.emoji, entry.role_id))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    e.colour(MAIN_COLOR)
+        .title(&menu.title)
+        .description(match roles.is_empty() {
+            true => "*No roles yet*".to_string(),
+            false => roles,
+        })
+        .footer(|f| f.text(menu.mode.describe()));
+
+    e
+}
+
+async fn get_role_names(ctx: &Context, menu: &RoleMenu) -> Vec<String> {
+    let mut names = vec![];
+    for entry in &menu.entries {
+        let name = ctx
+            .cache
+            .role(menu.guild_id, entry.role_id)
+            .await
+            .map(|role| role.name)
+            .unwrap_or_else(|| entry.role_id.to_string());
+        names.push(name);
+    }
+
+    names
+}
+
+/// Lists the roles of a menu in a select menu, so members can also pick roles without reacting.
+/// Menus without roles get no select menu, as it needs at least one option.
+fn add_select_menu<'a>(
+    components: &'a mut CreateComponents,
+    menu: &RoleMenu,
+    role_names: Vec<String>,
+) -> &'a mut CreateComponents {
+    if menu.entries.is_empty() {
+        return components;
+    }
+
+    components.create_action_row(|r| {
+        r.create_select_menu(|s| {
+            s.custom_id(SELECT_MENU_ID)
+                .placeholder("Pick a role")
+                .min_values(1)
+                .max_values(match menu.mode {
+                    RoleMenuMode::Unique => 1,
+                    _ => menu.entries.len() as u64,
+                })
+                .options(|o| {
+                    for (entry, name) in menu.entries.iter().zip(role_names) {
+                        o.create_option(|option| {
+                            option.label(name).value(entry.role_id);
+                            if let Ok(emoji) = ReactionType::from_str(&entry.emoji) {
+                                option.emoji(emoji);
+                            }
+                            option
+                        });
+                    }
+                    o
+                })
+        })
+    })
+}
+
+fn parse_menu_id(args: &mut Args) -> Result<MessageId, CommandError> {
+    args.single::<u64>()
+        .map(MessageId)
+        .map_err(|_| CommandError::from("Please supply the message id of the role menu"))
+}
+
+async fn send_confirmation(ctx: &Context, msg: &Message, description: String) {
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).description(description))
+        })
+        .await;
+}
@@ -1,4 +1,8 @@
-use crate::core::{constants::MAIN_COLOR, context::BotUserContainer, util::guild_icon_url};
+use crate::core::{
+    constants::MAIN_COLOR,
+    context::BotUserContainer,
+    util::{guild_icon_url, uppercase_first},
+};
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::Error;
@@ -10,47 +14,147 @@ use serenity::{
     framework::standard::{macros::command, Args, CommandError, CommandResult},
     futures::future::join_all,
     model::{
-        channel::{Embed, Message},
+        channel::{Embed, EmbedField, Message},
+        id::{ChannelId, GuildId, MessageId},
         prelude::User,
     },
 };
+use std::{collections::BTreeMap, iter};
+
+const INDEX_TITLE: &str = "Server Index";
+const TAGS_FIELD_NAME: &str = "Tags";
+const UNTAGGED_NAME: &str = "other";
+const MAX_EMBED_FIELDS: usize = 25;
+const MAX_EMBED_LENGTH: usize = 6000;
+const MAX_FIELD_NAME_LENGTH: usize = 256;
+const MAX_FIELD_VALUE_LENGTH: usize = 1024;
+const MAX_TAG_CHARS: usize = 32;
+// Messages per request when reading the channel history, which is the most discord allows
+const HISTORY_PAGE_SIZE: u64 = 100;
+// Room for the page numbers appended to the index title
+const PAGE_TITLE_RESERVE: usize = 10;
 
 lazy_static! {
-    // Regex to parse nhentai IDs from command input
-    static ref INVITE_ID_REGEX: Regex = Regex::new(r"discord\.gg/(\w+)").unwrap();
+    // Regex to parse discord invite IDs from command input
+    pub static ref INVITE_ID_REGEX: Regex = Regex::new(r"discord\.gg/(\w+)").unwrap();
 }
 
 #[command]
 #[aliases("sl")]
-#[sub_commands(add_server, sort_servers)]
+#[sub_commands(add_server, tag_server, remove_server, sort_servers, update_index)]
 #[description = "Provides various sub-commands to moderate a list of servers.\nRefer to the sub-commands for more info."]
 pub async fn serverlist() -> CommandResult {
     Ok(())
 }
 
 #[command("add")]
-#[description("Creates a serverlist embed for the given server in the current channel")]
-#[usage("<discord invite link>")]
+#[description(
+    "Creates a serverlist embed for the given server in the current channel. \
+    Any further arguments are used as tags to group the server in the index."
+)]
+#[usage("<discord invite link> [tags...]")]
 #[example("https://discord.gg/gochiusa")]
+#[example("https://discord.gg/gochiusa anime community")]
 #[min_args(1)]
-async fn add_server(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+async fn add_server(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     let _ = msg.delete(&ctx).await;
 
+    let invite_arg = args.single::<String>().unwrap();
     let invite_id = INVITE_ID_REGEX
-        .captures(args.rest())
+        .captures(&invite_arg)
         .and_then(|c| c.get(1))
         .ok_or_else(|| CommandError::from("Please supply a valid discord invite link"))?
         .as_str();
 
+    let tags = parse_tags(args)?;
     let invite_info: InviteInfo = get_invite_info(invite_id).await?;
 
     let _ = msg
         .channel_id
         .send_message(&ctx.http, |m| {
-            m.embed(|e| create_server_embed(e, &invite_info, &msg.author))
+            m.embed(|e| create_server_embed(e, &invite_info, &msg.author, &tags))
         })
         .await;
 
+    update_server_index(ctx, msg.guild_id.unwrap(), msg.channel_id).await?;
+
+    Ok(())
+}
+
+#[command("tag")]
+#[description(
+    "Replaces the tags of an existing serverlist embed in this channel. \
+    Leave out the tags to remove all of them."
+)]
+#[usage("<message id> [tags...]")]
+#[example("725681148134424596 anime gaming")]
+#[min_args(1)]
+async fn tag_server(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let _ = msg.delete(&ctx).await;
+
+    let message_id = args
+        .single::<u64>()
+        .map_err(|_| CommandError::from("Please supply the id of a serverlist message"))?;
+
+    let mut entry = msg.channel_id.message(&ctx.http, message_id).await?;
+
+    if !is_server_entry(ctx, &entry).await {
+        return Err(CommandError::from("That message is not a serverlist entry"));
+    }
+
+    let tags = parse_tags(args)?;
+    let mut embed = entry.embeds[0].clone();
+
+    embed.fields.retain(|f| f.name != TAGS_FIELD_NAME);
+    if !tags.is_empty() {
+        embed
+            .fields
+            .push(EmbedField::new(TAGS_FIELD_NAME, tags.join(", "), false));
+    }
+
+    entry
+        .edit(&ctx, |m| m.set_embed(CreateEmbed::from(embed)))
+        .await?;
+
+    update_server_index(ctx, msg.guild_id.unwrap(), msg.channel_id).await?;
+
+    Ok(())
+}
+
+#[command("remove")]
+#[description("Deletes a serverlist embed from this channel and updates the index")]
+#[usage("<message id>")]
+#[example("725681148134424596")]
+#[min_args(1)]
+async fn remove_server(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let _ = msg.delete(&ctx).await;
+
+    let message_id = args
+        .single::<u64>()
+        .map_err(|_| CommandError::from("Please supply the id of a serverlist message"))?;
+
+    let entry = msg.channel_id.message(&ctx.http, message_id).await?;
+
+    if !is_server_entry(ctx, &entry).await {
+        return Err(CommandError::from("That message is not a serverlist entry"));
+    }
+
+    entry.delete(&ctx).await?;
+
+    update_server_index(ctx, msg.guild_id.unwrap(), msg.channel_id).await?;
+
+    Ok(())
+}
+
+#[command("index")]
+#[description(
+    "Regenerates the pinned index of all serverlist embeds in this channel, grouped by tag"
+)]
+async fn update_index(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let _ = msg.delete(&ctx).await;
+
+    update_server_index(ctx, msg.guild_id.unwrap(), msg.channel_id).await?;
+
     Ok(())
 }
 
@@ -59,33 +163,250 @@ async fn add_server(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 async fn sort_servers(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
     let _ = msg.delete(&ctx).await;
 
+    let mut messages = get_server_entries(ctx, msg.channel_id).await?;
+
+    let mut embeds: Vec<Embed> = messages.iter().map(|m| m.embeds[0].clone()).collect();
+
+    embeds.sort_by_cached_key(|e| e.title.clone().unwrap_or_default());
+
+    let futures = messages.iter_mut().map(|m| {
+        let embed = embeds.pop().unwrap();
+        m.edit(&ctx, |m| m.set_embed(CreateEmbed::from(embed)))
+    });
+
+    join_all(futures).await;
+
+    update_server_index(ctx, msg.guild_id.unwrap(), msg.channel_id).await?;
+
+    Ok(())
+}
+
+/// Turns the remaining command arguments into a list of unique, lowercase tags
+fn parse_tags(mut args: Args) -> Result<Vec<String>, CommandError> {
+    let mut tags: Vec<String> = args
+        .iter::<String>()
+        .filter_map(|t| t.ok())
+        .map(|t| t.trim_matches(',').to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    if tags.iter().any(|t| t.chars().count() > MAX_TAG_CHARS) {
+        return Err(CommandError::from(format!(
+            "Tags can be at most {} characters long",
+            MAX_TAG_CHARS
+        )));
+    }
+
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Reads the tags back from a serverlist embed
+fn get_entry_tags(embed: &Embed) -> Vec<String> {
+    embed
+        .fields
+        .iter()
+        .find(|f| f.name == TAGS_FIELD_NAME)
+        .map(|f| f.value.split(", ").map(String::from).collect())
+        .unwrap_or_default()
+}
+
+async fn is_server_entry(ctx: &Context, message: &Message) -> bool {
     let ctx_data = ctx.data.read().await;
     let bot_user = ctx_data
         .get::<BotUserContainer>()
         .expect("Couldn't get bot user from context.");
 
-    let mut messages: Vec<Message> = msg
-        .channel_id
-        .messages(&ctx.http, |retriever| retriever.limit(100))
+    message.author.id == bot_user.id
+        && message
+            .embeds
+            .first()
+            .is_some_and(|e| !is_index_embed(Some(e)))
+}
+
+/// Collects all serverlist embeds posted by the bot in the given channel, newest first
+async fn get_server_entries(
+    ctx: &Context,
+    channel_id: ChannelId,
+) -> Result<Vec<Message>, CommandError> {
+    let mut entries = Vec::new();
+    let mut before: Option<MessageId> = None;
+
+    // The history is read page by page, going back from the newest message
+    loop {
+        let messages = channel_id
+            .messages(&ctx.http, |retriever| match before {
+                Some(message_id) => retriever.before(message_id).limit(HISTORY_PAGE_SIZE),
+                None => retriever.limit(HISTORY_PAGE_SIZE),
+            })
+            .await?;
+
+        let is_last_page = (messages.len() as u64) < HISTORY_PAGE_SIZE;
+        before = messages.last().map(|m| m.id);
+
+        for message in messages {
+            if is_server_entry(ctx, &message).await {
+                entries.push(message);
+            }
+        }
+
+        if is_last_page || before.is_none() {
+            return Ok(entries);
+        }
+    }
+}
+
+/// Rebuilds the index embeds that group all serverlist entries of a channel by their tags.
+/// The index is kept as pinned bot messages, which are created on first use.
+/// It's split into pages, as a single embed only holds 25 fields and 6000 characters.
+async fn update_server_index(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> CommandResult {
+    let entries = get_server_entries(ctx, channel_id).await?;
+
+    // Group entries by tag, sorted alphabetically by tag and server name
+    let mut groups: BTreeMap<String, Vec<(String, MessageId)>> = BTreeMap::new();
+    for entry in &entries {
+        let embed = &entry.embeds[0];
+        let name = embed.title.clone().unwrap_or_default();
+        let mut tags = get_entry_tags(embed);
+
+        if tags.is_empty() {
+            tags.push(UNTAGGED_NAME.to_string());
+        }
+
+        for tag in tags {
+            groups
+                .entry(tag)
+                .or_default()
+                .push((name.clone(), entry.id));
+        }
+    }
+
+    // Tags with too many servers for one field continue in further fields
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for (tag, mut servers) in groups {
+        servers.sort();
+
+        // Entries tagged before tag lengths were limited might exceed the field name limit
+        let tag_name = truncate_chars(&uppercase_first(&tag), MAX_FIELD_NAME_LENGTH);
+        let mut value = String::new();
+        for (name, message_id) in servers {
+            let line = format!(
+                "[{}](https://discord.com/channels/{}/{}/{})\n",
+                escape_link_text(&name),
+                guild_id.0,
+                channel_id.0,
+                message_id.0
+            );
+
+            if value.len() + line.len() > MAX_FIELD_VALUE_LENGTH {
+                fields.push((tag_name.clone(), std::mem::take(&mut value)));
+            }
+            value.push_str(&line);
+        }
+        fields.push((tag_name, value));
+    }
+
+    let description = match entries.len() {
+        0 => "There are no servers in this list yet.".to_string(),
+        n => format!("**{}** servers, grouped by their tags.", n),
+    };
+
+    // Fill each page with fields until either embed limit is reached
+    let mut pages: Vec<Vec<(String, String)>> = vec![vec![]];
+    let mut page_length = INDEX_TITLE.len() + PAGE_TITLE_RESERVE + description.len();
+    for field in fields {
+        let field_length = field.0.len() + field.1.len();
+        let page = pages.last_mut().unwrap();
+
+        if page.len() == MAX_EMBED_FIELDS || page_length + field_length > MAX_EMBED_LENGTH {
+            pages.push(vec![field]);
+            page_length = INDEX_TITLE.len() + PAGE_TITLE_RESERVE + field_length;
+        } else {
+            page_length += field_length;
+            page.push(field);
+        }
+    }
+
+    let bot_id = ctx.cache.current_user_id().await;
+    let mut index_messages: Vec<Message> = channel_id
+        .pins(&ctx.http)
         .await?
         .into_iter()
-        .filter(|msg| msg.author.id == bot_user.id && msg.embeds.len() > 0)
+        .filter(|m| m.author.id == bot_id && is_index_embed(m.embeds.first()))
         .collect();
+    index_messages.sort_by_key(|m| m.id);
 
-    let mut embeds: Vec<Embed> = messages.iter().map(|m| m.embeds[0].clone()).collect();
+    let page_count = pages.len();
+    let mut index_messages = index_messages.into_iter();
 
-    embeds.sort_by_cached_key(|e| e.title.clone().unwrap_or_default());
+    for (page_index, page) in pages.into_iter().enumerate() {
+        let title = match page_count {
+            1 => INDEX_TITLE.to_string(),
+            _ => format!("{} ({}/{})", INDEX_TITLE, page_index + 1, page_count),
+        };
+        let page_fields = page
+            .into_iter()
+            .map(|(name, value)| (name, value, false))
+            .collect::<Vec<_>>();
 
-    let futures = messages.iter_mut().map(|m| {
-        let embed = embeds.pop().unwrap();
-        m.edit(&ctx, |m| m.set_embed(CreateEmbed::from(embed)))
-    });
+        let mut embed = CreateEmbed::default();
+        embed.colour(MAIN_COLOR).title(title).fields(page_fields);
 
-    join_all(futures).await;
+        // Only the first page carries the description, so it's not repeated
+        if page_index == 0 {
+            embed.description(&description);
+        }
+
+        match index_messages.next() {
+            Some(mut index_message) => {
+                index_message.edit(&ctx, |m| m.set_embed(embed)).await?;
+            }
+            None => {
+                let index_message = channel_id
+                    .send_message(&ctx.http, |m| m.set_embed(embed))
+                    .await?;
+
+                index_message.pin(&ctx).await?;
+            }
+        }
+    }
+
+    // Pages that aren't needed anymore after servers were removed
+    for index_message in index_messages {
+        index_message.delete(&ctx).await?;
+    }
 
     Ok(())
 }
 
+fn is_index_embed(embed: Option<&Embed>) -> bool {
+    embed
+        .and_then(|e| e.title.as_deref())
+        .is_some_and(|title| title.starts_with(INDEX_TITLE))
+}
+
+/// Escapes brackets, so server names can't end the text of a markdown link
+// Cuts off texts that are longer than the given amount of characters with an ellipsis
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.chars().count() > max_chars {
+        true => text
+            .chars()
+            .take(max_chars - 1)
+            .chain(iter::once('…'))
+            .collect(),
+        false => text.to_string(),
+    }
+}
+
+fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
 async fn get_invite_info(invite_id: &str) -> Result<InviteInfo, Error> {
     let client = reqwest::Client::new();
     client
@@ -103,6 +424,7 @@ fn create_server_embed<'a>(
     e: &'a mut CreateEmbed,
     info: &InviteInfo,
     author: &User,
+    tags: &[String],
 ) -> &'a mut CreateEmbed {
     e.color(MAIN_COLOR)
         .title(&info.guild.name)
@@ -120,6 +442,10 @@ fn create_server_embed<'a>(
         e.thumbnail(guild_icon_url(&info.guild.id, icon_id, 64));
     }
 
+    if !tags.is_empty() {
+        e.field(TAGS_FIELD_NAME, tags.join(", "), false);
+    }
+
     e.footer(|f| {
         if info.expires_at.is_some() {
             f.text(format!("{}   Expires on", &author.name));
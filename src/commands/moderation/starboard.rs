@@ -0,0 +1,435 @@
+use crate::core::{
+    constants::MAIN_COLOR,
+    guild_config::{update_guild_config, GuildConfigs},
+    storage::get_store,
+    util::{is_guild_channel, parse_emoji, reaction_matches},
+};
+use lazy_static::lazy_static;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::{Message, Reaction, ReactionType},
+        id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+    },
+    prelude::Context,
+};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::Mutex as AsyncMutex;
+
+const DEFAULT_STAR_EMOJI: &str = "⭐";
+const DEFAULT_STAR_THRESHOLD: u64 = 3;
+// Starboard embeds show the beginning of long messages only
+const MAX_STARRED_CONTENT_CHARS: usize = 2000;
+
+lazy_static! {
+    // Locks of the messages whose reactions are being handled
+    static ref MESSAGE_LOCKS: Mutex<HashMap<u64, Arc<AsyncMutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct StarboardData {
+    guilds: HashMap<u64, StarboardConfig>,
+}
+
+impl GuildConfigs for StarboardData {
+    type Config = StarboardConfig;
+
+    fn configs_mut(&mut self) -> &mut HashMap<u64, StarboardConfig> {
+        &mut self.guilds
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarboardConfig {
+    channel_id: Option<u64>,
+    emoji: String,
+    threshold: u64,
+    // Starboard message ids by the id of the starred message
+    posts: HashMap<u64, u64>,
+}
+
+impl Default for StarboardConfig {
+    fn default() -> Self {
+        StarboardConfig {
+            channel_id: None,
+            emoji: DEFAULT_STAR_EMOJI.to_string(),
+            threshold: DEFAULT_STAR_THRESHOLD,
+            posts: HashMap::new(),
+        }
+    }
+}
+
+#[command]
+#[sub_commands(starboard_channel, starboard_emoji, starboard_threshold)]
+#[description(
+    "Shows the starboard settings. Messages that collect enough star reactions \
+    are reposted to the starboard channel."
+)]
+pub async fn starboard(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let fields = {
+        let store = get_store::<StarboardData>(ctx).await;
+        let store = store.read().await;
+        let default_config = StarboardConfig::default();
+        let config = store.guilds.get(&guild_id.0).unwrap_or(&default_config);
+
+        vec![
+            (
+                "Channel",
+                match config.channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => "off".to_string(),
+                },
+                true,
+            ),
+            ("Emoji", config.emoji.clone(), true),
+            ("Threshold", config.threshold.to_string(), true),
+            ("Starred messages", config.posts.len().to_string(), true),
+        ]
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).title("Starboard").fields(fields))
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("channel")]
+#[description("Sets the channel that starred messages are posted to")]
+#[usage("<#channel|off>")]
+#[example("#starboard")]
+#[min_args(1)]
+async fn starboard_channel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel_id = match args.current() {
+        Some("off") => None,
+        _ => Some(args.single::<ChannelId>().map_err(|_| {
+            CommandError::from("Please supply a valid channel mention or id, or `off`")
+        })?),
+    };
+
+    if let Some(channel_id) = channel_id {
+        if !is_guild_channel(ctx, msg.guild_id.unwrap(), channel_id).await {
+            return Err(CommandError::from("Please supply a channel of this server"));
+        }
+    }
+
+    update_guild_config::<StarboardData, _>(ctx, msg, |config| {
+        config.channel_id = channel_id.map(|c| c.0);
+        // Posts of the old channel can't be updated anymore
+        config.posts.clear();
+
+        Ok(match channel_id {
+            Some(channel_id) => format!("Starred messages are posted to <#{}>", channel_id.0),
+            None => "The starboard is turned off".to_string(),
+        })
+    })
+    .await
+}
+
+#[command("emoji")]
+#[description("Sets the emoji that is used to star messages")]
+#[usage("<emoji>")]
+#[example("🌟")]
+#[min_args(1)]
+async fn starboard_emoji(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let emoji = args
+        .single::<String>()
+        .ok()
+        .and_then(|e| parse_emoji(&e))
+        .ok_or_else(|| CommandError::from("Please supply a valid emoji"))?;
+
+    update_guild_config::<StarboardData, _>(ctx, msg, |config| {
+        config.emoji = emoji.to_string();
+        Ok(format!("Messages are now starred with {}", emoji))
+    })
+    .await
+}
+
+#[command("threshold")]
+#[description("Sets how many reactions a message needs to be posted to the starboard")]
+#[usage("<amount>")]
+#[example("5")]
+#[min_args(1)]
+async fn starboard_threshold(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let threshold = args
+        .single::<u64>()
+        .ok()
+        .filter(|t| *t > 0)
+        .ok_or_else(|| CommandError::from("Please supply a valid amount of at least 1"))?;
+
+    update_guild_config::<StarboardData, _>(ctx, msg, |config| {
+        config.threshold = threshold;
+        Ok(format!(
+            "Messages now need **{}** reactions to be starred",
+            threshold
+        ))
+    })
+    .await
+}
+
+/// Posts, updates or removes the starboard entry of the reacted message
+pub async fn handle_reaction(ctx: &Context, reaction: &Reaction) {
+    // Reactions on the same message are handled one after the other, so that reactions coming
+    // in at the same time can't post the message twice
+    let lock = message_lock(reaction.message_id);
+    {
+        let _guard = lock.lock().await;
+        update_post(ctx, reaction).await;
+    }
+
+    drop(lock);
+    let mut locks = MESSAGE_LOCKS.lock().unwrap();
+    if locks
+        .get(&reaction.message_id.0)
+        .is_some_and(|lock| Arc::strong_count(lock) == 1)
+    {
+        locks.remove(&reaction.message_id.0);
+    }
+}
+
+fn message_lock(message_id: MessageId) -> Arc<AsyncMutex<()>> {
+    MESSAGE_LOCKS
+        .lock()
+        .unwrap()
+        .entry(message_id.0)
+        .or_default()
+        .clone()
+}
+
+async fn update_post(ctx: &Context, reaction: &Reaction) {
+    let guild_id = match reaction.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    // The store isn't locked while talking to discord, so only a copy of the settings is used
+    let (star_channel_id, emoji, threshold, existing_post) = {
+        let store = get_store::<StarboardData>(ctx).await;
+        let store = store.read().await;
+
+        match store.guilds.get(&guild_id.0) {
+            Some(config) => (
+                config.channel_id,
+                config.emoji.clone(),
+                config.threshold,
+                config
+                    .posts
+                    .get(&reaction.message_id.0)
+                    .copied()
+                    .map(MessageId),
+            ),
+            None => return,
+        }
+    };
+
+    let star_channel_id = match star_channel_id {
+        Some(channel_id) if channel_id != reaction.channel_id.0 => ChannelId(channel_id),
+        _ => return,
+    };
+
+    let star_emoji = match ReactionType::from_str(&emoji) {
+        Ok(emoji) if reaction_matches(&emoji, &reaction.emoji) => emoji,
+        _ => return,
+    };
+
+    if !is_visible_on_starboard(ctx, guild_id, reaction.channel_id, star_channel_id).await {
+        return;
+    }
+
+    let message = match reaction.message(&ctx.http).await {
+        Ok(message) => message,
+        Err(_) => return,
+    };
+
+    let mut star_count = message
+        .reactions
+        .iter()
+        .find(|r| reaction_matches(&r.reaction_type, &star_emoji))
+        .map(|r| r.count)
+        .unwrap_or_default();
+
+    // Authors starring their own message don't count
+    if star_count >= threshold && has_starred_own_message(ctx, &message, &star_emoji).await {
+        star_count -= 1;
+    }
+
+    let header = format!("{} **{}** <#{}>", emoji, star_count, reaction.channel_id.0);
+
+    let post_id = match existing_post {
+        Some(post_id) if star_count < threshold => {
+            let _ = star_channel_id.delete_message(&ctx.http, post_id).await;
+            None
+        }
+        Some(post_id) => {
+            let _ = star_channel_id
+                .edit_message(&ctx.http, post_id, |m| m.content(&header))
+                .await;
+            return;
+        }
+        None if star_count >= threshold => {
+            let embed = create_star_embed(guild_id, &message);
+
+            match star_channel_id
+                .send_message(&ctx.http, |m| m.content(&header).set_embed(embed))
+                .await
+            {
+                Ok(post) => Some(post.id.0),
+                Err(why) => {
+                    error!(
+                        "Couldn't post message {} to starboard: {:?}",
+                        message.id, why
+                    );
+                    return;
+                }
+            }
+        }
+        None => return,
+    };
+
+    let store = get_store::<StarboardData>(ctx).await;
+    let mut store = store.write().await;
+
+    // The starboard might have been moved or turned off in the meantime
+    let config = match store.guilds.get_mut(&guild_id.0) {
+        Some(config) if config.channel_id == Some(star_channel_id.0) => config,
+        _ => return,
+    };
+
+    match post_id {
+        Some(post_id) => config.posts.insert(message.id.0, post_id),
+        None => config.posts.remove(&message.id.0),
+    };
+    store.save();
+}
+
+// Messages of NSFW channels, or of channels that are hidden from members who can see the
+// starboard, are never reposted
+async fn is_visible_on_starboard(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    star_channel_id: ChannelId,
+) -> bool {
+    let (channel, star_channel) = match (
+        ctx.cache.guild_channel(channel_id).await,
+        ctx.cache.guild_channel(star_channel_id).await,
+    ) {
+        (Some(channel), Some(star_channel)) => (channel, star_channel),
+        _ => return false,
+    };
+
+    if channel.is_nsfw() {
+        return false;
+    }
+
+    let everyone_role_id = RoleId(guild_id.0);
+    let is_public = channel
+        .permissions_for_role(&ctx.cache, everyone_role_id)
+        .await
+        .is_ok_and(|p| p.read_messages());
+    let is_starboard_public = star_channel
+        .permissions_for_role(&ctx.cache, everyone_role_id)
+        .await
+        .is_ok_and(|p| p.read_messages());
+
+    is_public || !is_starboard_public
+}
+
+async fn has_starred_own_message(
+    ctx: &Context,
+    message: &Message,
+    star_emoji: &ReactionType,
+) -> bool {
+    // Users are listed by id, so the first user after the id right below the author's is the
+    // author, if they reacted
+    let after = UserId(message.author.id.0 - 1);
+
+    message
+        .reaction_users(&ctx.http, star_emoji.clone(), Some(1), after)
+        .await
+        .is_ok_and(|users| users.first().map(|u| u.id) == Some(message.author.id))
+}
+
+/// Removes the starboard entry of a message whose reactions have all been removed
+pub async fn handle_reaction_remove_all(ctx: &Context, message_id: MessageId) {
+    let store = get_store::<StarboardData>(ctx).await;
+    let mut store = store.write().await;
+
+    let (channel_id, post_id) = match store.guilds.values_mut().find_map(|config| {
+        config
+            .posts
+            .remove(&message_id.0)
+            .map(|post_id| (config.channel_id, post_id))
+    }) {
+        Some((Some(channel_id), post_id)) => (ChannelId(channel_id), post_id),
+        _ => return,
+    };
+
+    store.save();
+    let _ = channel_id.delete_message(&ctx.http, post_id).await;
+}
+
+fn create_star_embed(guild_id: GuildId, message: &Message) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.colour(MAIN_COLOR)
+        .author(|a| a.name(message.author.tag()).icon_url(message.author.face()))
+        .field(
+            "Source",
+            format!(
+                "[Jump to message](https://discord.com/channels/{}/{}/{})",
+                guild_id.0, message.channel_id.0, message.id.0
+            ),
+            false,
+        )
+        .footer(|f| f.text(format!("Message ID: {}", message.id.0)))
+        .timestamp(&message.timestamp);
+
+    if !message.content.is_empty() {
+        e.description(
+            match message
+                .content
+                .char_indices()
+                .nth(MAX_STARRED_CONTENT_CHARS)
+            {
+                Some((index, _)) => format!("{}…", &message.content[..index]),
+                None => message.content.clone(),
+            },
+        );
+    }
+
+    // Show the first image, either from the attachments or from embeds like image links
+    let image_url = message
+        .attachments
+        .iter()
+        .find(|a| a.width.is_some())
+        .map(|a| a.url.clone())
+        .or_else(|| {
+            message.embeds.iter().find_map(|embed| {
+                embed
+                    .image
+                    .as_ref()
+                    .map(|i| i.url.clone())
+                    .or_else(|| embed.thumbnail.as_ref().map(|t| t.url.clone()))
+            })
+        });
+
+    if let Some(image_url) = image_url {
+        e.image(image_url);
+    }
+
+    e
+}
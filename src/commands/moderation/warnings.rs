@@ -0,0 +1,559 @@
+use super::cases::{create_case, CaseAction};
+use crate::core::{
+    constants::MAIN_COLOR,
+    moderation::{check_hierarchy, notify_user, timeout_member, DEFAULT_REASON},
+    storage::get_store,
+    util::{format_duration, parse_duration},
+};
+use chrono::{DateTime, Duration, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        id::{GuildId, UserId},
+    },
+    prelude::Context,
+};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct WarningData {
+    guilds: HashMap<u64, GuildWarnings>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct GuildWarnings {
+    next_id: u64,
+    infractions: Vec<Infraction>,
+    rules: Vec<EscalationRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Infraction {
+    id: u64,
+    user_id: u64,
+    moderator_id: u64,
+    reason: String,
+    timestamp: DateTime<Utc>,
+}
+
+/// An action that is taken automatically once a user reaches the given amount of warnings
+#[derive(Serialize, Deserialize, Clone)]
+struct EscalationRule {
+    warnings: usize,
+    action: EscalationAction,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+enum EscalationAction {
+    Timeout { seconds: i64 },
+    Kick,
+    Ban,
+}
+
+impl EscalationAction {
+    fn describe(&self) -> String {
+        match self {
+            EscalationAction::Timeout { seconds } => {
+                format!(
+                    "timeout for {}",
+                    format_duration(Duration::seconds(*seconds))
+                )
+            }
+            EscalationAction::Kick => "kick".to_string(),
+            EscalationAction::Ban => "ban".to_string(),
+        }
+    }
+}
+
+#[command]
+#[description(
+    "Warns a member and records the infraction. \
+    If the member reaches the amount of warnings of an escalation rule, its action is applied."
+)]
+#[usage("<user> [reason]")]
+#[example("@user Please stay on topic")]
+#[min_args(1)]
+pub async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user_id = parse_user_id_arg(&mut args)?;
+    let reason = match args.rest() {
+        "" => DEFAULT_REASON,
+        reason => reason,
+    };
+
+    let user = user_id
+        .to_user(ctx)
+        .await
+        .map_err(|_| CommandError::from("Couldn't find that user"))?;
+
+    // Escalations kick or ban as the bot, so only members below the moderator can be warned
+    check_hierarchy(ctx, guild_id, msg.author.id, user_id).await?;
+
+    let (warning_count, escalation) =
+        add_warning(ctx, guild_id, user_id, msg.author.id, reason).await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("Warned {}", user.tag()))
+                    .field("Reason", reason, false)
+                    .field("Total warnings", warning_count, true);
+
+                if let Some(escalation) = escalation {
+                    e.field("Escalation", escalation, true);
+                }
+
+                e
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("infractions")]
+#[description("Lists all recorded warnings of a user")]
+#[usage("<user>")]
+#[example("@user")]
+#[min_args(1)]
+pub async fn warnings(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user_id = parse_user_id_arg(&mut args)?;
+    let user = user_id
+        .to_user(ctx)
+        .await
+        .map_err(|_| CommandError::from("Couldn't find that user"))?;
+
+    let infractions: Vec<Infraction> = {
+        let store = get_store::<WarningData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .guilds
+            .get(&guild_id.0)
+            .map(|g| {
+                g.infractions
+                    .iter()
+                    .filter(|i| i.user_id == user_id.0)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("Warnings of {}", user.tag()))
+                    .description(format!("**{}** warnings in total", infractions.len()));
+
+                // Embeds are limited to 25 fields, so only show the latest warnings
+                for infraction in infractions.iter().rev().take(25) {
+                    e.field(
+                        format!(
+                            "#{} - {}",
+                            infraction.id,
+                            infraction.timestamp.format("%b %e %Y, %H:%M")
+                        ),
+                        format!("{}\nby <@{}>", infraction.reason, infraction.moderator_id),
+                        false,
+                    );
+                }
+
+                e
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Deletes a single warning by its id")]
+#[usage("<warning id>")]
+#[example("12")]
+#[min_args(1)]
+pub async fn delwarn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let warning_id = args
+        .single::<u64>()
+        .map_err(|_| CommandError::from("Please supply a valid warning id"))?;
+
+    {
+        let store = get_store::<WarningData>(ctx).await;
+        let mut store = store.write().await;
+
+        let guild_warnings = store.guilds.entry(guild_id.0).or_default();
+        let count_before = guild_warnings.infractions.len();
+        guild_warnings.infractions.retain(|i| i.id != warning_id);
+
+        if guild_warnings.infractions.len() == count_before {
+            return Err(CommandError::from(format!(
+                "There's no warning with the id {}",
+                warning_id
+            )));
+        }
+
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description(format!("Deleted warning #{}", warning_id))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[description("Deletes all warnings of a user")]
+#[usage("<user>")]
+#[example("@user")]
+#[min_args(1)]
+pub async fn clearwarns(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user_id = parse_user_id_arg(&mut args)?;
+
+    let removed_count = {
+        let store = get_store::<WarningData>(ctx).await;
+        let mut store = store.write().await;
+
+        let guild_warnings = store.guilds.entry(guild_id.0).or_default();
+        let count_before = guild_warnings.infractions.len();
+        guild_warnings
+            .infractions
+            .retain(|i| i.user_id != user_id.0);
+        let removed_count = count_before - guild_warnings.infractions.len();
+
+        store.save();
+        removed_count
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Deleted **{}** warnings of <@{}>",
+                    removed_count, user_id.0
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[sub_commands(set_rule, remove_rule)]
+#[description(
+    "Lists the escalation rules of this server. \
+    Escalation rules apply an action once a member reaches a certain amount of warnings."
+)]
+pub async fn warnrules(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let mut rules: Vec<EscalationRule> = {
+        let store = get_store::<WarningData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .guilds
+            .get(&guild_id.0)
+            .map(|g| g.rules.clone())
+            .unwrap_or_default()
+    };
+
+    rules.sort_by_key(|r| r.warnings);
+
+    let description = match rules.is_empty() {
+        true => "There are no escalation rules yet.\n\
+                Add one with `warnrules set <warnings> <timeout <duration>|kick|ban>`"
+            .to_string(),
+        false => rules
+            .iter()
+            .map(|r| format!("**{}** warnings → {}", r.warnings, r.action.describe()))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Escalation rules")
+                    .description(description)
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("set")]
+#[description(
+    "Sets the action that is applied once a member reaches the given amount of warnings. \
+    Actions are `timeout <duration>`, `kick` or `ban`."
+)]
+#[usage("<warnings> <timeout <duration>|kick|ban>")]
+#[example("3 timeout 1h")]
+#[example("5 ban")]
+#[min_args(2)]
+async fn set_rule(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let warnings = args
+        .single::<usize>()
+        .ok()
+        .filter(|w| *w > 0)
+        .ok_or_else(|| CommandError::from("Please supply a valid amount of warnings"))?;
+
+    let action = match args.single::<String>()?.to_lowercase().as_str() {
+        "timeout" | "mute" => {
+            let duration = args
+                .single::<String>()
+                .ok()
+                .and_then(|d| parse_duration(&d))
+                .filter(|d| *d <= Duration::days(28))
+                .ok_or_else(|| {
+                    CommandError::from("Please supply a valid timeout duration of up to 28 days")
+                })?;
+
+            EscalationAction::Timeout {
+                seconds: duration.num_seconds(),
+            }
+        }
+        "kick" => EscalationAction::Kick,
+        "ban" => EscalationAction::Ban,
+        _ => {
+            return Err(CommandError::from(
+                "The action must be one of `timeout <duration>`, `kick` or `ban`",
+            ))
+        }
+    };
+
+    let description = format!("**{}** warnings → {}", warnings, action.describe());
+
+    {
+        let store = get_store::<WarningData>(ctx).await;
+        let mut store = store.write().await;
+
+        let rules = &mut store.guilds.entry(guild_id.0).or_default().rules;
+        rules.retain(|r| r.warnings != warnings);
+        rules.push(EscalationRule { warnings, action });
+
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Escalation rule set")
+                    .description(description)
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("remove")]
+#[description("Removes the escalation rule for the given amount of warnings")]
+#[usage("<warnings>")]
+#[example("3")]
+#[min_args(1)]
+async fn remove_rule(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let warnings = args
+        .single::<usize>()
+        .map_err(|_| CommandError::from("Please supply a valid amount of warnings"))?;
+
+    {
+        let store = get_store::<WarningData>(ctx).await;
+        let mut store = store.write().await;
+
+        let rules = &mut store.guilds.entry(guild_id.0).or_default().rules;
+        let count_before = rules.len();
+        rules.retain(|r| r.warnings != warnings);
+
+        if rules.len() == count_before {
+            return Err(CommandError::from(format!(
+                "There's no escalation rule for {} warnings",
+                warnings
+            )));
+        }
+
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Removed the escalation rule for **{}** warnings",
+                    warnings
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Records a warning, notifies the user and applies a matching escalation rule.
+/// Returns the user's new warning count and a description of the applied escalation, if any.
+/// The role hierarchy isn't checked here, callers acting for a moderator have to do that first.
+pub async fn add_warning(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    moderator_id: UserId,
+    reason: &str,
+) -> (usize, Option<String>) {
+    let (warning_count, rule) = {
+        let store = get_store::<WarningData>(ctx).await;
+        let mut store = store.write().await;
+
+        let guild_warnings = store.guilds.entry(guild_id.0).or_default();
+        guild_warnings.next_id += 1;
+
+        let infraction = Infraction {
+            id: guild_warnings.next_id,
+            user_id: user_id.0,
+            moderator_id: moderator_id.0,
+            reason: reason.to_string(),
+            timestamp: Utc::now(),
+        };
+        guild_warnings.infractions.push(infraction);
+
+        let warning_count = guild_warnings
+            .infractions
+            .iter()
+            .filter(|i| i.user_id == user_id.0)
+            .count();
+
+        // Rules only trigger when the exact amount of warnings is reached,
+        // so each rule applies once per user
+        let rule = guild_warnings
+            .rules
+            .iter()
+            .find(|r| r.warnings == warning_count)
+            .cloned();
+
+        store.save();
+        (warning_count, rule)
+    };
+
+    notify_user(ctx, guild_id, user_id, "warned", reason).await;
+    create_case(
+        ctx,
+        guild_id,
+        CaseAction::Warn,
+        user_id,
+        moderator_id,
+        reason,
+        None,
+    )
+    .await;
+
+    let rule = match rule {
+        Some(rule) => rule,
+        None => return (warning_count, None),
+    };
+
+    // Escalations are done by the bot itself
+    let bot_id = ctx.cache.current_user_id().await;
+
+    let escalation_reason = format!("Reached {} warnings", warning_count);
+    let description = rule.action.describe();
+
+    let (result, case_action, duration) = match rule.action {
+        EscalationAction::Timeout { seconds } => {
+            let duration = Duration::seconds(seconds);
+            notify_user(
+                ctx,
+                guild_id,
+                user_id,
+                &format!("timed out for {}", format_duration(duration)),
+                &escalation_reason,
+            )
+            .await;
+
+            (
+                timeout_member(ctx, guild_id, user_id, Some(Utc::now() + duration)).await,
+                CaseAction::Timeout,
+                Some(duration),
+            )
+        }
+        EscalationAction::Kick => {
+            notify_user(ctx, guild_id, user_id, "kicked", &escalation_reason).await;
+
+            (
+                guild_id
+                    .kick_with_reason(&ctx.http, user_id, &escalation_reason)
+                    .await
+                    .map_err(CommandError::from),
+                CaseAction::Kick,
+                None,
+            )
+        }
+        EscalationAction::Ban => {
+            notify_user(ctx, guild_id, user_id, "banned", &escalation_reason).await;
+
+            (
+                guild_id
+                    .ban_with_reason(&ctx.http, user_id, 0, &escalation_reason)
+                    .await
+                    .map_err(CommandError::from),
+                CaseAction::Ban,
+                None,
+            )
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            create_case(
+                ctx,
+                guild_id,
+                case_action,
+                user_id,
+                bot_id,
+                &escalation_reason,
+                duration,
+            )
+            .await;
+
+            (warning_count, Some(description))
+        }
+        Err(why) => {
+            error!("Couldn't apply escalation rule to {}: {:?}", user_id, why);
+            (
+                warning_count,
+                Some(format!("Failed to apply {}", description)),
+            )
+        }
+    }
+}
+
+fn parse_user_id_arg(args: &mut Args) -> Result<UserId, CommandError> {
+    args.single::<UserId>()
+        .map_err(|_| CommandError::from("Please supply a valid user mention or id"))
+}
@@ -0,0 +1,434 @@
+use crate::core::{
+    constants::MAIN_COLOR,
+    guild_config::{on_off_str, parse_on_off, update_guild_config, GuildConfigs},
+    storage::get_store,
+    util::{format_duration, is_guild_channel, uppercase_first},
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId},
+        user::User,
+    },
+    prelude::Context,
+};
+use std::collections::HashMap;
+
+const DEFAULT_WELCOME_TEMPLATE: &str = "Welcome to **{server}**, {user}! You are member #{count}.";
+const DEFAULT_GOODBYE_TEMPLATE: &str = "**{username}** has left the server.";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct WelcomeData {
+    guilds: HashMap<u64, GuildGreetings>,
+}
+
+impl GuildConfigs for WelcomeData {
+    type Config = GuildGreetings;
+
+    fn configs_mut(&mut self) -> &mut HashMap<u64, GuildGreetings> {
+        &mut self.guilds
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct GuildGreetings {
+    welcome: Greeting,
+    goodbye: Greeting,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+struct Greeting {
+    channel_id: Option<u64>,
+    template: Option<String>,
+    embed: bool,
+    dm: bool,
+}
+
+#[derive(Clone, Copy)]
+enum GreetingKind {
+    Welcome,
+    Goodbye,
+}
+
+impl GreetingKind {
+    fn name(&self) -> &str {
+        match self {
+            GreetingKind::Welcome => "welcome",
+            GreetingKind::Goodbye => "goodbye",
+        }
+    }
+
+    fn default_template(&self) -> &str {
+        match self {
+            GreetingKind::Welcome => DEFAULT_WELCOME_TEMPLATE,
+            GreetingKind::Goodbye => DEFAULT_GOODBYE_TEMPLATE,
+        }
+    }
+
+    fn greeting<'a>(&self, greetings: &'a mut GuildGreetings) -> &'a mut Greeting {
+        match self {
+            GreetingKind::Welcome => &mut greetings.welcome,
+            GreetingKind::Goodbye => &mut greetings.goodbye,
+        }
+    }
+}
+
+#[command]
+#[sub_commands(
+    welcome_channel,
+    welcome_message,
+    welcome_embed,
+    welcome_dm,
+    welcome_test
+)]
+#[description(
+    "Shows the welcome message settings. New members are greeted in the welcome channel, \
+    and optionally via DM. Messages can contain the following placeholders: \n\
+    - `{user}`: mention of the member \n\
+    - `{username}`: name of the member \n\
+    - `{server}`: name of the server \n\
+    - `{count}`: member count of the server \n\
+    - `{age}`: age of the member's account"
+)]
+pub async fn welcome(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    show_settings(ctx, msg, GreetingKind::Welcome).await
+}
+
+#[command("channel")]
+#[description("Sets the channel that new members are welcomed in")]
+#[usage("<#channel|off>")]
+#[example("#welcome")]
+#[min_args(1)]
+async fn welcome_channel(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    set_channel(ctx, msg, args, GreetingKind::Welcome).await
+}
+
+#[command("message")]
+#[aliases("text")]
+#[description(
+    "Sets the welcome message. See `~help welcome` for the available placeholders. \
+    Use `reset` to restore the default message."
+)]
+#[usage("<message|reset>")]
+#[example("Hey {user}, welcome to {server}! Please read the rules first.")]
+#[min_args(1)]
+async fn welcome_message(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    set_template(ctx, msg, args, GreetingKind::Welcome).await
+}
+
+#[command("embed")]
+#[description("Sets whether the welcome message is sent as an embed")]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn welcome_embed(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let embed = parse_on_off(&mut args)?;
+
+    update_greeting(ctx, msg, GreetingKind::Welcome, |greeting| {
+        greeting.embed = embed;
+        match embed {
+            true => "Welcome messages are sent as embeds".to_string(),
+            false => "Welcome messages are sent as plain text".to_string(),
+        }
+    })
+    .await
+}
+
+#[command("dm")]
+#[description("Sets whether new members additionally receive the welcome message via DM")]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn welcome_dm(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let dm = parse_on_off(&mut args)?;
+
+    update_greeting(ctx, msg, GreetingKind::Welcome, |greeting| {
+        greeting.dm = dm;
+        match dm {
+            true => "New members receive the welcome message via DM".to_string(),
+            false => "New members don't receive the welcome message via DM".to_string(),
+        }
+    })
+    .await
+}
+
+#[command("test")]
+#[description("Shows the welcome message for yourself in this channel")]
+async fn welcome_test(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    test_greeting(ctx, msg, GreetingKind::Welcome).await
+}
+
+#[command]
+#[aliases("leave")]
+#[sub_commands(goodbye_channel, goodbye_message, goodbye_embed, goodbye_test)]
+#[description(
+    "Shows the goodbye message settings. Members that leave are announced in the goodbye channel. \
+    The message supports the same placeholders as the welcome message."
+)]
+pub async fn goodbye(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    show_settings(ctx, msg, GreetingKind::Goodbye).await
+}
+
+#[command("channel")]
+#[description("Sets the channel that leaving members are announced in")]
+#[usage("<#channel|off>")]
+#[example("#welcome")]
+#[min_args(1)]
+async fn goodbye_channel(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    set_channel(ctx, msg, args, GreetingKind::Goodbye).await
+}
+
+#[command("message")]
+#[aliases("text")]
+#[description(
+    "Sets the goodbye message. See `~help welcome` for the available placeholders. \
+    Use `reset` to restore the default message."
+)]
+#[usage("<message|reset>")]
+#[example("{username} left us after {age} on discord :(")]
+#[min_args(1)]
+async fn goodbye_message(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    set_template(ctx, msg, args, GreetingKind::Goodbye).await
+}
+
+#[command("embed")]
+#[description("Sets whether the goodbye message is sent as an embed")]
+#[usage("<on|off>")]
+#[min_args(1)]
+async fn goodbye_embed(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let embed = parse_on_off(&mut args)?;
+
+    update_greeting(ctx, msg, GreetingKind::Goodbye, |greeting| {
+        greeting.embed = embed;
+        match embed {
+            true => "Goodbye messages are sent as embeds".to_string(),
+            false => "Goodbye messages are sent as plain text".to_string(),
+        }
+    })
+    .await
+}
+
+#[command("test")]
+#[description("Shows the goodbye message for yourself in this channel")]
+async fn goodbye_test(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    test_greeting(ctx, msg, GreetingKind::Goodbye).await
+}
+
+/// Greets a new member in the welcome channel and via DM, if enabled
+pub async fn send_welcome(ctx: &Context, guild_id: GuildId, user: &User) {
+    send_greeting(ctx, guild_id, user, GreetingKind::Welcome).await;
+}
+
+/// Announces a leaving member in the goodbye channel, if enabled
+pub async fn send_goodbye(ctx: &Context, guild_id: GuildId, user: &User) {
+    send_greeting(ctx, guild_id, user, GreetingKind::Goodbye).await;
+}
+
+async fn send_greeting(ctx: &Context, guild_id: GuildId, user: &User, kind: GreetingKind) {
+    let greeting = match get_greeting(ctx, guild_id, kind).await {
+        Some(greeting) => greeting,
+        None => return,
+    };
+
+    let text = render_template(ctx, guild_id, user, &greeting, kind).await;
+
+    if let Some(channel_id) = greeting.channel_id {
+        let _ = ChannelId(channel_id)
+            .send_message(&ctx.http, |m| match greeting.embed {
+                true => m.embed(|e| {
+                    e.colour(MAIN_COLOR)
+                        .thumbnail(user.face())
+                        .description(&text)
+                }),
+                false => m.content(&text),
+            })
+            .await;
+    }
+
+    if greeting.dm {
+        let _ = user
+            .direct_message(ctx, |m| match greeting.embed {
+                true => m.embed(|e| e.colour(MAIN_COLOR).description(&text)),
+                false => m.content(&text),
+            })
+            .await;
+    }
+}
+
+async fn get_greeting(ctx: &Context, guild_id: GuildId, kind: GreetingKind) -> Option<Greeting> {
+    let store = get_store::<WelcomeData>(ctx).await;
+    let store = store.read().await;
+
+    store.guilds.get(&guild_id.0).map(|greetings| match kind {
+        GreetingKind::Welcome => greetings.welcome.clone(),
+        GreetingKind::Goodbye => greetings.goodbye.clone(),
+    })
+}
+
+async fn render_template(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    greeting: &Greeting,
+    kind: GreetingKind,
+) -> String {
+    let guild_name = guild_id.name(&ctx.cache).await.unwrap_or_default();
+    let member_count = ctx
+        .cache
+        .guild_field(guild_id, |g| g.member_count)
+        .await
+        .unwrap_or_default();
+
+    // Accounts older than a day don't need to show the exact hours
+    let account_age = Utc::now().signed_duration_since(user.id.created_at());
+    let account_age = match account_age.num_days() {
+        0 => format_duration(Duration::hours(account_age.num_hours())),
+        1 => "1 day".to_string(),
+        days => format!("{} days", days),
+    };
+
+    greeting
+        .template
+        .as_deref()
+        .unwrap_or_else(|| kind.default_template())
+        .replace("{user}", &format!("<@{}>", user.id.0))
+        .replace("{username}", &user.name)
+        .replace("{server}", &guild_name)
+        .replace("{count}", &member_count.to_string())
+        .replace("{age}", &account_age)
+}
+
+async fn show_settings(ctx: &Context, msg: &Message, kind: GreetingKind) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let greeting = get_greeting(ctx, guild_id, kind).await.unwrap_or_default();
+
+    let mut fields = vec![
+        (
+            "Channel",
+            match greeting.channel_id {
+                Some(channel_id) => format!("<#{}>", channel_id),
+                None => "off".to_string(),
+            },
+            true,
+        ),
+        ("Embed", on_off_str(greeting.embed).to_string(), true),
+    ];
+
+    if let GreetingKind::Welcome = kind {
+        fields.push(("DM", on_off_str(greeting.dm).to_string(), true));
+    }
+
+    fields.push((
+        "Message",
+        greeting
+            .template
+            .unwrap_or_else(|| kind.default_template().to_string()),
+        false,
+    ));
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("{} message settings", uppercase_first(kind.name())))
+                    .fields(fields)
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+async fn set_channel(
+    ctx: &Context,
+    msg: &Message,
+    mut args: Args,
+    kind: GreetingKind,
+) -> CommandResult {
+    let channel_id = match args.current() {
+        Some("off") => None,
+        _ => Some(args.single::<ChannelId>().map_err(|_| {
+            CommandError::from("Please supply a valid channel mention or id, or `off`")
+        })?),
+    };
+
+    if let Some(channel_id) = channel_id {
+        if !is_guild_channel(ctx, msg.guild_id.unwrap(), channel_id).await {
+            return Err(CommandError::from("Please supply a channel of this server"));
+        }
+    }
+
+    update_greeting(ctx, msg, kind, |greeting| {
+        greeting.channel_id = channel_id.map(|c| c.0);
+        match channel_id {
+            Some(channel_id) => format!(
+                "{} messages are sent to <#{}>",
+                uppercase_first(kind.name()),
+                channel_id.0
+            ),
+            None => format!("{} messages are turned off", uppercase_first(kind.name())),
+        }
+    })
+    .await
+}
+
+async fn set_template(
+    ctx: &Context,
+    msg: &Message,
+    args: Args,
+    kind: GreetingKind,
+) -> CommandResult {
+    let template = match args.rest() {
+        "reset" => None,
+        template => Some(template.to_string()),
+    };
+
+    update_greeting(ctx, msg, kind, |greeting| {
+        greeting.template = template;
+        format!(
+            "Updated the {} message. Use `{} test` to see how it looks.",
+            kind.name(),
+            kind.name()
+        )
+    })
+    .await
+}
+
+async fn test_greeting(ctx: &Context, msg: &Message, kind: GreetingKind) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let greeting = get_greeting(ctx, guild_id, kind).await.unwrap_or_default();
+    let text = render_template(ctx, guild_id, &msg.author, &greeting, kind).await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| match greeting.embed {
+            true => m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .thumbnail(msg.author.face())
+                    .description(&text)
+            }),
+            false => m.content(&text),
+        })
+        .await;
+
+    Ok(())
+}
+
+async fn update_greeting<F>(
+    ctx: &Context,
+    msg: &Message,
+    kind: GreetingKind,
+    update: F,
+) -> CommandResult
+where
+    F: FnOnce(&mut Greeting) -> String,
+{
+    update_guild_config::<WelcomeData, _>(ctx, msg, |greetings| {
+        Ok(update(kind.greeting(greetings)))
+    })
+    .await
+}
@@ -4,7 +4,7 @@ use sysinfo::{ProcessExt, ProcessorExt, SystemExt};
 
 use crate::core::{
     constants::MAIN_COLOR,
-    context::{StartTimeContainer, SysInfoContainer},
+    context::{StartTimeContainer, SysInfoContainer, WeatherCacheContainer},
 };
 
 use chrono::Utc;
@@ -40,6 +40,13 @@ pub async fn info(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
     let start_time = data.get::<StartTimeContainer>().unwrap();
     let bot_uptime = Utc::now().signed_duration_since(*start_time).num_seconds();
 
+    let weather_cache = data
+        .get::<WeatherCacheContainer>()
+        .unwrap()
+        .lock()
+        .await
+        .stats();
+
     // System info
     let sys = data.get::<SysInfoContainer>().unwrap();
     let cpu = sys.global_processor_info();
@@ -70,12 +77,16 @@ pub async fn info(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
                             **Compiled with**: rustc v{}\n\
                             **Owner**: {}\n\
                             **Mem usage**: {:.2} MB\n\
-                            **Uptime**: {}",
+                            **Uptime**: {}\n\
+                            **Weather cache**: {} hits, {} misses ({} entries)",
                             BOT_VERSION,
                             version(),
                             bot_owner,
                             bot_process.memory() / 1024,
                             get_formatted_uptime(bot_uptime as u64),
+                            weather_cache.hits,
+                            weather_cache.misses,
+                            weather_cache.entries,
 
                         ), false),
                     ("System Info", 
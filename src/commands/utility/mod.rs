@@ -0,0 +1,11 @@
+use serenity::framework::standard::macros::group;
+
+mod remind;
+
+use self::remind::{REMINDERS_COMMAND, REMIND_COMMAND, TIMEZONE_COMMAND};
+
+pub use self::remind::{ReminderData, ReminderExecutor};
+
+#[group]
+#[commands(remind, reminders, timezone)]
+struct Utility;
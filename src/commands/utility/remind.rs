@@ -0,0 +1,389 @@
+use crate::core::{
+    constants::MAIN_COLOR,
+    scheduler::{cancel, get_tasks, schedule, ActionExecutor, ScheduledAction, ScheduledTask},
+    storage::get_store,
+    util::{format_duration, parse_duration},
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+    prelude::{Context, SerenityError},
+};
+use std::collections::HashMap;
+
+const MAX_REMINDERS_PER_USER: usize = 25;
+const DM_FLAG: &str = "--dm";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ReminderData {
+    // IANA timezone names by user id
+    timezones: HashMap<u64, String>,
+}
+
+#[command]
+#[aliases("remindme")]
+#[description(
+    "Reminds you of something after a duration or at a given time. \
+    Times are in your timezone, which you can set with `timezone`. \
+    The reminder is sent in this channel, or via DM if you add `--dm`."
+)]
+#[usage("[me] <in <duration>|at <time>> [to] <text> [--dm]")]
+#[example("me in 2h to check the oven")]
+#[example("me at 18:00 to call mom")]
+#[example("me at 2021-12-24 10:00 to buy presents --dm")]
+#[min_args(3)]
+pub async fn remind(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if args.current() == Some("me") {
+        args.advance();
+    }
+
+    let due = match args.single::<String>()?.to_lowercase().as_str() {
+        "in" => {
+            let duration = args
+                .single::<String>()
+                .ok()
+                .and_then(|d| parse_duration(&d))
+                .ok_or_else(|| {
+                    CommandError::from(
+                        "Please supply a valid duration of up to 5 years, i.e. `2h30m`",
+                    )
+                })?;
+            Utc::now() + duration
+        }
+        "at" => {
+            let timezone = get_timezone(ctx, msg.author.id).await;
+            parse_time(&mut args, timezone)?
+        }
+        _ => {
+            return Err(CommandError::from(
+                "Please use either `in <duration>` or `at <time>`",
+            ))
+        }
+    };
+
+    if args.current() == Some("to") {
+        args.advance();
+    }
+
+    let mut words: Vec<&str> = args.rest().split(' ').collect();
+    let dm = words.contains(&DM_FLAG);
+    words.retain(|w| *w != DM_FLAG);
+    let text = words.join(" ").trim().to_string();
+
+    if text.is_empty() {
+        return Err(CommandError::from(
+            "Please supply what you want to be reminded of",
+        ));
+    }
+
+    let user_id = msg.author.id.0;
+    let reminder_count = get_tasks(ctx, |t| is_reminder_of(t, user_id)).await.len();
+    if reminder_count >= MAX_REMINDERS_PER_USER {
+        return Err(CommandError::from(format!(
+            "You can't have more than {} reminders",
+            MAX_REMINDERS_PER_USER
+        )));
+    }
+
+    // Reminders from DMs can only be delivered via DM
+    let channel_id = match dm || msg.guild_id.is_none() {
+        true => None,
+        false => Some(msg.channel_id.0),
+    };
+
+    let id = schedule(
+        ctx,
+        due,
+        ScheduledAction::Reminder {
+            user_id,
+            channel_id,
+            text,
+            created: Utc::now(),
+        },
+    )
+    .await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "I'll remind you in **{}** (reminder #{})",
+                    format_duration(round_to_minutes(due - Utc::now())),
+                    id
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[sub_commands(reminders_list, reminders_delete)]
+#[description("Lists your pending reminders")]
+pub async fn reminders(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    list_reminders(ctx, msg).await
+}
+
+#[command("list")]
+#[description("Lists your pending reminders")]
+async fn reminders_list(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    list_reminders(ctx, msg).await
+}
+
+#[command("delete")]
+#[aliases("remove", "cancel")]
+#[description("Deletes one of your reminders")]
+#[usage("<reminder id>")]
+#[example("42")]
+#[min_args(1)]
+async fn reminders_delete(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let id = args
+        .single::<String>()
+        .ok()
+        .and_then(|id| id.trim_start_matches('#').parse::<u64>().ok())
+        .ok_or_else(|| CommandError::from("Please supply a valid reminder id"))?;
+
+    let user_id = msg.author.id.0;
+    if !cancel(ctx, id, |t| is_reminder_of(t, user_id)).await {
+        return Err(CommandError::from(format!(
+            "You don't have a reminder #{}",
+            id
+        )));
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description(format!("Deleted reminder #{}", id))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("tz")]
+#[description(
+    "Shows or sets your timezone, which is used for times in your reminders. \
+    Timezones are names of the tz database, so daylight saving time is taken into account."
+)]
+#[usage("[timezone]")]
+#[example("Europe/Berlin")]
+#[example("America/New_York")]
+pub async fn timezone(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !args.is_empty() {
+        let timezone = Tz::from_str_insensitive(args.rest().trim()).map_err(|_| {
+            CommandError::from("Please supply a valid timezone name, i.e. `Europe/Berlin`")
+        })?;
+
+        let store = get_store::<ReminderData>(ctx).await;
+        let mut store = store.write().await;
+        store
+            .timezones
+            .insert(msg.author.id.0, timezone.name().to_string());
+        store.save();
+    }
+
+    let timezone = get_timezone(ctx, msg.author.id).await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Your timezone is **{}**, your local time is **{}**",
+                    timezone.name(),
+                    Utc::now().with_timezone(&timezone).format("%H:%M %Z")
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Sends reminders once they're due
+pub struct ReminderExecutor;
+
+#[async_trait]
+impl ActionExecutor for ReminderExecutor {
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError> {
+        match action {
+            ScheduledAction::Reminder {
+                user_id,
+                channel_id,
+                text,
+                created,
+            } => {
+                deliver_reminder(
+                    ctx,
+                    UserId(*user_id),
+                    channel_id.map(ChannelId),
+                    text,
+                    *created,
+                )
+                .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Sends a due reminder to its channel. Falls back to a DM if the channel isn't available anymore.
+async fn deliver_reminder(
+    ctx: &Context,
+    user_id: UserId,
+    channel_id: Option<ChannelId>,
+    text: &str,
+    created: DateTime<Utc>,
+) -> Result<(), SerenityError> {
+    let description = format!(
+        "{}\n\n*Reminder from {} ago*",
+        text,
+        format_duration(round_to_minutes(Utc::now() - created))
+    );
+
+    if let Some(channel_id) = channel_id {
+        let sent = channel_id
+            .send_message(&ctx.http, |m| {
+                m.content(format!("<@{}>", user_id.0)).embed(|e| {
+                    e.colour(MAIN_COLOR)
+                        .title("Reminder")
+                        .description(&description)
+                })
+            })
+            .await;
+
+        if sent.is_ok() {
+            return Ok(());
+        }
+    }
+
+    user_id
+        .create_dm_channel(ctx)
+        .await?
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title("Reminder")
+                    .description(&description)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn list_reminders(ctx: &Context, msg: &Message) -> CommandResult {
+    let user_id = msg.author.id.0;
+    let reminders = get_tasks(ctx, |t| is_reminder_of(t, user_id)).await;
+    let timezone = get_timezone(ctx, msg.author.id).await;
+
+    let description = reminders
+        .iter()
+        .filter_map(|task| match &task.action {
+            ScheduledAction::Reminder { text, .. } => Some(format!(
+                "**#{}** {}: {}",
+                task.id,
+                task.due
+                    .with_timezone(&timezone)
+                    .format("%Y-%m-%d %H:%M %Z"),
+                text
+            )),
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).title("Your reminders").description(
+                    match description.is_empty() {
+                        true => "You don't have any reminders".to_string(),
+                        false => description,
+                    },
+                )
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+fn is_reminder_of(task: &ScheduledTask, user_id: u64) -> bool {
+    matches!(task.action, ScheduledAction::Reminder { user_id: id, .. } if id == user_id)
+}
+
+async fn get_timezone(ctx: &Context, user_id: UserId) -> Tz {
+    let store = get_store::<ReminderData>(ctx).await;
+    let store = store.read().await;
+
+    store
+        .timezones
+        .get(&user_id.0)
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// Parses a time like "18:00" or "2021-12-24 18:00" in the user's timezone.
+/// Times without a date refer to the next time it's that time of day.
+fn parse_time(args: &mut Args, timezone: Tz) -> Result<DateTime<Utc>, CommandError> {
+    let invalid_time = || CommandError::from("Please supply a valid time, i.e. `18:00`");
+
+    let first = args.single::<String>().map_err(|_| invalid_time())?;
+    let now = Utc::now().with_timezone(&timezone);
+
+    let (date, time) = match NaiveDate::parse_from_str(&first, "%Y-%m-%d") {
+        Ok(date) => {
+            let time = args
+                .single::<String>()
+                .ok()
+                .and_then(|t| NaiveTime::parse_from_str(&t, "%H:%M").ok())
+                .ok_or_else(invalid_time)?;
+            (date, time)
+        }
+        Err(_) => {
+            let time = NaiveTime::parse_from_str(&first, "%H:%M").map_err(|_| invalid_time())?;
+            match time > now.time() {
+                true => (now.date().naive_local(), time),
+                false => (now.date().naive_local() + Duration::days(1), time),
+            }
+        }
+    };
+
+    // Times that occur twice when the clocks go back refer to the first one,
+    // times skipped when they go forward are invalid
+    let due = timezone
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(invalid_time)?
+        .with_timezone(&Utc);
+
+    if due <= Utc::now() {
+        return Err(CommandError::from("That time is in the past"));
+    }
+
+    Ok(due)
+}
+
+// Longer durations are shown in minutes, as a few seconds pass between scheduling and showing them
+fn round_to_minutes(duration: Duration) -> Duration {
+    match duration < Duration::minutes(1) {
+        true => duration,
+        false => Duration::minutes((duration.num_seconds() as f64 / 60.0).round() as i64),
+    }
+}
@@ -11,14 +11,14 @@ enum Unit {
     Temperature(TemperatureType),
 }
 
-enum VelocityType {
+pub(super) enum VelocityType {
     KILOMETERSPERHOUR,
     MILESPERHOUR,
     METERSPERSECOND,
     FEETPERSECOND,
 }
 
-enum DistanceType {
+pub(super) enum DistanceType {
     KILOMETER,
     METER,
     CENTIMETER,
@@ -29,7 +29,7 @@ enum DistanceType {
     INCH,
 }
 
-enum TemperatureType {
+pub(super) enum TemperatureType {
     CELSIUS,
     KELVIN,
     FAHRENHEIT,
@@ -273,3 +273,22 @@ fn do_conversion(
 ) -> f64 {
     matrix[matrix_source_index][matrix_dest_index](number)
 }
+
+/// Converts a velocity between two units
+pub(super) fn convert_velocity(number: f64, source: VelocityType, dest: VelocityType) -> f64 {
+    do_conversion(&VELOCITY_MATRIX, number, source as usize, dest as usize)
+}
+
+/// Converts a distance between two units
+pub(super) fn convert_distance(number: f64, source: DistanceType, dest: DistanceType) -> f64 {
+    do_conversion(&DISTANCE_MATRIX, number, source as usize, dest as usize)
+}
+
+/// Converts a temperature between two units
+pub(super) fn convert_temperature(
+    number: f64,
+    source: TemperatureType,
+    dest: TemperatureType,
+) -> f64 {
+    do_conversion(&TEMP_MATRIX, number, source as usize, dest as usize)
+}
@@ -10,6 +10,9 @@ use self::tldr::TLDR_COMMAND;
 use self::translate::TRANSLATE_COMMAND;
 use self::weather::WEATHER_COMMAND;
 
+pub use self::translate::{handle_flag_reaction, TranslateData};
+pub use self::weather::{ForecastExecutor, WeatherData};
+
 #[group]
 #[commands(convert, weather, tldr, translate)]
 struct Web;
@@ -0,0 +1,143 @@
+use serde::Deserialize;
+use serenity::{async_trait, framework::standard::CommandError};
+use std::env;
+
+use super::provider::{map_language, Translation, TranslationProvider};
+
+pub const DEEPL_API_KEY_VAR: &str = "DEEPL_API_KEY";
+
+const MAX_CHUNK_CHARS: usize = 5000;
+
+// Codes of the language table and DeepL's codes of the languages it translates from
+const SOURCE_LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "AR"),
+    ("bg", "BG"),
+    ("cs", "CS"),
+    ("da", "DA"),
+    ("de", "DE"),
+    ("el", "EL"),
+    ("en", "EN"),
+    ("es", "ES"),
+    ("et", "ET"),
+    ("fi", "FI"),
+    ("fr", "FR"),
+    ("hu", "HU"),
+    ("id", "ID"),
+    ("it", "IT"),
+    ("ja", "JA"),
+    ("ko", "KO"),
+    ("lt", "LT"),
+    ("lv", "LV"),
+    ("no", "NB"),
+    ("nl", "NL"),
+    ("pl", "PL"),
+    ("pt", "PT"),
+    ("ro", "RO"),
+    ("ru", "RU"),
+    ("sk", "SK"),
+    ("sl", "SL"),
+    ("sv", "SV"),
+    ("tr", "TR"),
+    ("uk", "UK"),
+    ("zh", "ZH"),
+];
+
+// Target languages have regional variants, of which the most common one is used
+const TARGET_VARIANTS: &[(&str, &str)] = &[
+    ("en", "EN-US"),
+    ("pt", "PT-PT"),
+    ("zh", "ZH-HANS"),
+    ("zh-TW", "ZH-HANT"),
+];
+
+pub struct DeepL {
+    api_key: String,
+}
+
+impl DeepL {
+    pub fn from_env() -> Result<Self, CommandError> {
+        let api_key = env::var(DEEPL_API_KEY_VAR).map_err(|_| {
+            CommandError::from("The bot owner didn't provide the DeepL api key".to_string())
+        })?;
+
+        Ok(DeepL { api_key })
+    }
+
+    // Keys of the free plan only work with the free api
+    fn api_url(&self) -> &'static str {
+        match self.api_key.ends_with(":fx") {
+            true => "https://api-free.deepl.com/v2/translate",
+            false => "https://api.deepl.com/v2/translate",
+        }
+    }
+}
+
+#[async_trait]
+impl TranslationProvider for DeepL {
+    fn max_chunk_chars(&self) -> usize {
+        MAX_CHUNK_CHARS
+    }
+
+    fn source_code(&self, lang: &str) -> Option<&'static str> {
+        map_language(SOURCE_LANGUAGES, lang)
+    }
+
+    fn target_code(&self, lang: &str) -> Option<&'static str> {
+        map_language(TARGET_VARIANTS, lang).or_else(|| map_language(SOURCE_LANGUAGES, lang))
+    }
+
+    async fn translate(
+        &self,
+        client: &reqwest::Client,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<Translation, CommandError> {
+        let mut form = vec![("text", text), ("target_lang", target_lang)];
+        if let Some(source_lang) = source_lang {
+            form.push(("source_lang", source_lang));
+        }
+
+        let response = client
+            .post(self.api_url())
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .form(&form)
+            .send()
+            .await?;
+
+        match response.status().as_u16() {
+            403 => return Err(CommandError::from("The DeepL api key is invalid")),
+            456 => return Err(CommandError::from("The DeepL translation quota is used up")),
+            _ => (),
+        }
+
+        let translation = response
+            .error_for_status()?
+            .json::<TranslateResponse>()
+            .await
+            .map_err(|_| CommandError::from("There was an error parsing the DeepL response"))?
+            .translations
+            .into_iter()
+            .next()
+            .ok_or_else(|| CommandError::from("DeepL didn't return a translation"))?;
+
+        // DeepL neither tells how confident its detection is nor which languages came close
+        Ok(Translation {
+            text: translation.text,
+            source_lang: translation.detected_source_language,
+            confidence: None,
+            alternatives: vec![],
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TranslateResponse {
+    translations: Vec<TranslatedText>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TranslatedText {
+    detected_source_language: String,
+    text: String,
+}
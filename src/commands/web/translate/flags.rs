@@ -0,0 +1,161 @@
+// Flags of Wales and Scotland are tag sequences instead of regional indicator pairs
+const WALES_FLAG: &str = "🏴\u{e0067}\u{e0062}\u{e0077}\u{e006c}\u{e0073}\u{e007f}";
+const SCOTLAND_FLAG: &str = "🏴\u{e0067}\u{e0062}\u{e0073}\u{e0063}\u{e0074}\u{e007f}";
+
+// Country codes and the codes of the language table for the most spoken language there.
+// Countries without a single predominant language are left out.
+const COUNTRY_LANGUAGES: &[(&str, &str)] = &[
+    ("AD", "ca"),
+    ("AE", "ar"),
+    ("AF", "ps"),
+    ("AL", "sq"),
+    ("AM", "hy"),
+    ("AO", "pt"),
+    ("AR", "es"),
+    ("AT", "de"),
+    ("AU", "en"),
+    ("AZ", "az"),
+    ("BA", "bs"),
+    ("BD", "bn"),
+    ("BG", "bg"),
+    ("BH", "ar"),
+    ("BO", "es"),
+    ("BR", "pt"),
+    ("BY", "be"),
+    ("CA", "en"),
+    ("CH", "de"),
+    ("CL", "es"),
+    ("CN", "zh"),
+    ("CO", "es"),
+    ("CR", "es"),
+    ("CU", "es"),
+    ("CY", "el"),
+    ("CZ", "cs"),
+    ("DE", "de"),
+    ("DK", "da"),
+    ("DO", "es"),
+    ("DZ", "ar"),
+    ("EC", "es"),
+    ("EE", "et"),
+    ("EG", "ar"),
+    ("ES", "es"),
+    ("ET", "am"),
+    ("FI", "fi"),
+    ("FR", "fr"),
+    ("GB", "en"),
+    ("GE", "ka"),
+    ("GR", "el"),
+    ("GT", "es"),
+    ("HK", "zh-TW"),
+    ("HN", "es"),
+    ("HR", "hr"),
+    ("HT", "ht"),
+    ("HU", "hu"),
+    ("ID", "id"),
+    ("IE", "ga"),
+    ("IL", "iw"),
+    ("IN", "hi"),
+    ("IQ", "ar"),
+    ("IR", "fa"),
+    ("IS", "is"),
+    ("IT", "it"),
+    ("JO", "ar"),
+    ("JP", "ja"),
+    ("KE", "sw"),
+    ("KG", "ky"),
+    ("KH", "km"),
+    ("KP", "ko"),
+    ("KR", "ko"),
+    ("KW", "ar"),
+    ("KZ", "kk"),
+    ("LA", "lo"),
+    ("LB", "ar"),
+    ("LI", "de"),
+    ("LK", "si"),
+    ("LS", "st"),
+    ("LT", "lt"),
+    ("LU", "lb"),
+    ("LV", "lv"),
+    ("LY", "ar"),
+    ("MA", "ar"),
+    ("MD", "ro"),
+    ("ME", "sr"),
+    ("MG", "mg"),
+    ("MK", "mk"),
+    ("MM", "my"),
+    ("MN", "mn"),
+    ("MT", "mt"),
+    ("MW", "ny"),
+    ("MX", "es"),
+    ("MY", "ms"),
+    ("MZ", "pt"),
+    ("NI", "es"),
+    ("NL", "nl"),
+    ("NO", "no"),
+    ("NP", "ne"),
+    ("NZ", "en"),
+    ("OM", "ar"),
+    ("PA", "es"),
+    ("PE", "es"),
+    ("PH", "tl"),
+    ("PK", "ur"),
+    ("PL", "pl"),
+    ("PT", "pt"),
+    ("PY", "es"),
+    ("QA", "ar"),
+    ("RO", "ro"),
+    ("RS", "sr"),
+    ("RU", "ru"),
+    ("RW", "rw"),
+    ("SA", "ar"),
+    ("SD", "ar"),
+    ("SE", "sv"),
+    ("SI", "sl"),
+    ("SK", "sk"),
+    ("SM", "it"),
+    ("SO", "so"),
+    ("SV", "es"),
+    ("SY", "ar"),
+    ("TH", "th"),
+    ("TJ", "tg"),
+    ("TM", "tk"),
+    ("TN", "ar"),
+    ("TR", "tr"),
+    ("TW", "zh-TW"),
+    ("TZ", "sw"),
+    ("UA", "uk"),
+    ("US", "en"),
+    ("UY", "es"),
+    ("UZ", "uz"),
+    ("VA", "it"),
+    ("VE", "es"),
+    ("VN", "vi"),
+    ("WS", "sm"),
+    ("XK", "sq"),
+    ("YE", "ar"),
+    ("ZW", "sn"),
+];
+
+/// Returns the language table code of the language spoken in the country of a flag emoji
+pub fn flag_language(emoji: &str) -> Option<&'static str> {
+    match emoji {
+        WALES_FLAG => return Some("cy"),
+        SCOTLAND_FLAG => return Some("gd"),
+        _ => (),
+    }
+
+    // A flag consists of two regional indicator symbols, which stand for the letters A to Z
+    let country_code = emoji
+        .chars()
+        .map(|c| match c {
+            '\u{1f1e6}'..='\u{1f1ff}' => char::from_u32(c as u32 - 0x1f1e6 + 'A' as u32),
+            _ => None,
+        })
+        .collect::<Option<String>>()
+        .filter(|code| code.len() == 2)?;
+
+    COUNTRY_LANGUAGES
+        .iter()
+        .find(|(country, _)| *country == country_code)
+        .map(|(_, lang)| *lang)
+}
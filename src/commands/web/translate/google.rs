@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use serenity::{async_trait, framework::standard::CommandError};
+
+use super::{
+    provider::{Translation, TranslationProvider},
+    validate_lang_arg,
+};
+
+// Texts are sent in the query string, which the endpoint only accepts up to a certain length
+const MAX_CHUNK_CHARS: usize = 1000;
+
+/// The unofficial endpoint of Google Translate, which the language table is made for
+pub struct Google;
+
+#[async_trait]
+impl TranslationProvider for Google {
+    fn max_chunk_chars(&self) -> usize {
+        MAX_CHUNK_CHARS
+    }
+
+    fn source_code(&self, lang: &str) -> Option<&'static str> {
+        validate_lang_arg(lang)
+    }
+
+    fn target_code(&self, lang: &str) -> Option<&'static str> {
+        validate_lang_arg(lang)
+    }
+
+    async fn translate(
+        &self,
+        client: &reqwest::Client,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<Translation, CommandError> {
+        // dj=1 makes the endpoint answer with an object instead of nested arrays
+        let response = client
+            .get("https://translate.googleapis.com/translate_a/single")
+            .query(&[
+                ("client", "gtx"),
+                ("sl", source_lang.unwrap_or("auto")),
+                ("tl", target_lang),
+                ("dt", "t"),
+                ("dj", "1"),
+                ("q", text),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            // Errors of reqwest contain the url, which has the text in its query string
+            .map_err(|why| {
+                CommandError::from(match why.status() {
+                    Some(status) => format!("Google Translate answered with {}", status),
+                    None => "Google Translate couldn't be reached".to_string(),
+                })
+            })?
+            .json::<TranslationResponse>()
+            .await
+            .map_err(|_| {
+                CommandError::from("There was an error parsing the translation response")
+            })?;
+
+        let src = response.src;
+        let alternatives = response
+            .ld_result
+            .map(|result| {
+                result
+                    .srclangs
+                    .into_iter()
+                    .zip(result.srclangs_confidences)
+                    .filter(|(lang, _)| !lang.eq_ignore_ascii_case(&src))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Translation {
+            text: response
+                .sentences
+                .into_iter()
+                .map(|sentence| sentence.trans)
+                .collect(),
+            source_lang: src,
+            confidence: response.confidence,
+            alternatives,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TranslationResponse {
+    #[serde(default)]
+    sentences: Vec<TranslatedSentence>,
+    src: String,
+    confidence: Option<f64>,
+    ld_result: Option<LanguageDetection>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TranslatedSentence {
+    // Transliteration entries come without a translation
+    #[serde(default)]
+    trans: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LanguageDetection {
+    #[serde(default)]
+    srclangs: Vec<String>,
+    #[serde(default)]
+    srclangs_confidences: Vec<f64>,
+}
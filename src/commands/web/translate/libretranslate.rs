@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use serenity::{async_trait, framework::standard::CommandError};
+use std::env;
+
+use super::provider::{map_language, Translation, TranslationProvider};
+
+pub const LIBRETRANSLATE_URL_VAR: &str = "LIBRETRANSLATE_URL";
+pub const LIBRETRANSLATE_API_KEY_VAR: &str = "LIBRETRANSLATE_API_KEY";
+
+// Instances limit the characters per request, 2000 is a common setting
+const MAX_CHUNK_CHARS: usize = 2000;
+
+// Codes of the language table and LibreTranslate's codes of the languages its default models
+// translate between. Instances with fewer models installed answer with an error instead.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "ar"),
+    ("az", "az"),
+    ("bg", "bg"),
+    ("bn", "bn"),
+    ("ca", "ca"),
+    ("cs", "cs"),
+    ("da", "da"),
+    ("de", "de"),
+    ("el", "el"),
+    ("en", "en"),
+    ("eo", "eo"),
+    ("es", "es"),
+    ("et", "et"),
+    ("eu", "eu"),
+    ("fa", "fa"),
+    ("fi", "fi"),
+    ("fr", "fr"),
+    ("ga", "ga"),
+    ("gl", "gl"),
+    ("iw", "he"),
+    ("hi", "hi"),
+    ("hu", "hu"),
+    ("id", "id"),
+    ("it", "it"),
+    ("ja", "ja"),
+    ("ko", "ko"),
+    ("lt", "lt"),
+    ("lv", "lv"),
+    ("ms", "ms"),
+    ("no", "nb"),
+    ("nl", "nl"),
+    ("pl", "pl"),
+    ("pt", "pt"),
+    ("ro", "ro"),
+    ("ru", "ru"),
+    ("sk", "sk"),
+    ("sl", "sl"),
+    ("sq", "sq"),
+    ("sv", "sv"),
+    ("th", "th"),
+    ("tl", "tl"),
+    ("tr", "tr"),
+    ("uk", "uk"),
+    ("ur", "ur"),
+    ("zh", "zh"),
+    ("zh-TW", "zt"),
+];
+
+/// A self-hosted LibreTranslate instance
+pub struct LibreTranslate {
+    url: String,
+    api_key: Option<String>,
+}
+
+impl LibreTranslate {
+    pub fn from_env() -> Result<Self, CommandError> {
+        let url = env::var(LIBRETRANSLATE_URL_VAR).map_err(|_| {
+            CommandError::from(
+                "The bot owner didn't provide the url of a LibreTranslate instance".to_string(),
+            )
+        })?;
+
+        Ok(LibreTranslate {
+            url: url.trim_end_matches('/').to_string(),
+            api_key: env::var(LIBRETRANSLATE_API_KEY_VAR).ok(),
+        })
+    }
+}
+
+#[async_trait]
+impl TranslationProvider for LibreTranslate {
+    fn max_chunk_chars(&self) -> usize {
+        MAX_CHUNK_CHARS
+    }
+
+    fn source_code(&self, lang: &str) -> Option<&'static str> {
+        map_language(LANGUAGES, lang)
+    }
+
+    fn target_code(&self, lang: &str) -> Option<&'static str> {
+        map_language(LANGUAGES, lang)
+    }
+
+    async fn translate(
+        &self,
+        client: &reqwest::Client,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<Translation, CommandError> {
+        let response = client
+            .post(format!("{}/translate", self.url))
+            .json(&TranslateRequest {
+                q: text,
+                source: source_lang.unwrap_or("auto"),
+                target: target_lang,
+                format: "text",
+                api_key: self.api_key.as_deref(),
+            })
+            .send()
+            .await?;
+
+        // Errors come with a message, i.e. for languages the instance has no model for
+        if !response.status().is_success() {
+            let error = response
+                .json::<ErrorResponse>()
+                .await
+                .map(|response| response.error)
+                .unwrap_or_else(|_| "The request failed".to_string());
+
+            return Err(CommandError::from(format!("LibreTranslate: {}", error)));
+        }
+
+        let response = response.json::<TranslateResponse>().await.map_err(|_| {
+            CommandError::from("There was an error parsing the LibreTranslate response")
+        })?;
+
+        // The source language is only returned when it was detected
+        let (source_lang, confidence) = match response.detected_language {
+            Some(detected) => (detected.language, Some(detected.confidence / 100.0)),
+            None => (source_lang.unwrap_or_default().to_string(), None),
+        };
+
+        Ok(Translation {
+            text: response.translated_text,
+            source_lang,
+            confidence,
+            alternatives: vec![],
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct TranslateRequest<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: String,
+    detected_language: Option<DetectedLanguage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DetectedLanguage {
+    // In percent
+    confidence: f64,
+    language: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    error: String,
+}
@@ -0,0 +1,655 @@
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    model::{
+        channel::{Message, Reaction, ReactionType},
+        id::{ChannelId, GuildId, UserId},
+    },
+    prelude::Context,
+};
+use std::{collections::HashMap, sync::Mutex};
+
+mod deepl;
+mod flags;
+mod google;
+mod libretranslate;
+mod provider;
+
+use self::flags::flag_language;
+use self::provider::{Backend, Translation};
+use crate::core::{
+    checks::ADMIN_CHECK, constants::MAIN_COLOR, context::HttpClientContainer, storage::get_store,
+};
+
+const MAX_EMBED_DESCRIPTION_CHARS: usize = 4096;
+const SENTENCE_ENDS: [char; 7] = ['.', '!', '?', '\n', '。', '！', '？'];
+const MAX_ALTERNATIVES: usize = 3;
+// Users can have one message translated with a flag reaction within this timespan
+const FLAG_COOLDOWN_SECS: i64 = 30;
+
+lazy_static! {
+    // When each user last had a message translated with a flag reaction
+    static ref FLAG_TRANSLATIONS: Mutex<HashMap<u64, DateTime<Utc>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TranslateData {
+    // Chosen translation backend by guild id
+    backends: HashMap<u64, Backend>,
+}
+
+#[command]
+#[sub_commands(translate_provider, translate_languages)]
+#[description(
+    "Translates a given text into the target language given as the first argument. \
+        You can optionally prefix the source language as first argument, \
+        otherwise it will be auto detected. \
+        When used as a reply without a text, the replied message is translated. \
+        Reacting to a message with a country flag translates it into that country's language."
+)]
+#[usage("<target lang> <text>")]
+#[usage("<source lang> <target lang> <text>")]
+#[example("en こんにちは！")]
+#[example("de en Guten Abend!")]
+#[min_args(1)]
+pub async fn translate(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    // checked by min_args macro
+    let first_arg = args.single::<String>().unwrap();
+    let second_arg = args.single::<String>().unwrap_or_default();
+
+    // Get the target lang (or source lang if second language is given)
+    let mut target_lang = match validate_lang_arg(&first_arg) {
+        Some(lang) => lang,
+        None => {
+            return Err(CommandError::from(
+                "The first argument must be a valid language name or code!\n\
+                Use `translate languages` to list the languages that can be translated",
+            ))
+        }
+    };
+
+    // Try to grab a second language parameter. On success, use that as the target_lang and the
+    // initial first parameter as source language (i.e. switch from <target> <text> to <source> <target> <text>)
+    let source_lang = match validate_lang_arg(&second_arg) {
+        Some(lang) => {
+            // When the second argument is a language,
+            // swap first and second arguments
+            let target_lang_copy = target_lang;
+            target_lang = lang;
+            Some(target_lang_copy)
+        }
+        None => {
+            // Else write this argument back to args, as it's part of the translation string!
+            if !second_arg.is_empty() {
+                args.rewind();
+            }
+            None
+        }
+    };
+
+    // Without a text, the message that is replied to gets translated
+    let text = match (args.is_empty(), &msg.referenced_message) {
+        (false, _) => args.rest(),
+        (true, Some(referenced)) if !referenced.content.trim().is_empty() => &referenced.content,
+        (true, Some(_)) => {
+            return Err(CommandError::from(
+                "The message you replied to has no text to be translated",
+            ))
+        }
+        (true, None) => {
+            return Err(CommandError::from(
+                "Please supply a text to be translated or reply to a message",
+            ))
+        }
+    };
+
+    let translated = translate_text(ctx, msg.guild_id, source_lang, target_lang, text).await?;
+    send_translation(
+        ctx,
+        msg.channel_id,
+        None,
+        source_lang,
+        target_lang,
+        &translated,
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Translates a message into the language of the country whose flag it was reacted with
+pub async fn handle_flag_reaction(ctx: &Context, reaction: &Reaction) {
+    let target_lang = match &reaction.emoji {
+        ReactionType::Unicode(emoji) => match flag_language(emoji) {
+            Some(lang) => lang,
+            None => return,
+        },
+        _ => return,
+    };
+
+    let user_id = match reaction.user_id {
+        Some(user_id) if user_id != ctx.cache.current_user_id().await => user_id,
+        _ => return,
+    };
+
+    // Every translation is a request to the provider, which might be a paid one
+    if is_on_flag_cooldown(user_id) {
+        return;
+    }
+
+    let message = match reaction.message(&ctx.http).await {
+        Ok(message) => message,
+        Err(_) => return,
+    };
+
+    // Only the first reaction with a flag is answered, so the same translation isn't posted twice
+    let is_first_reaction = message
+        .reactions
+        .iter()
+        .find(|r| r.reaction_type == reaction.emoji)
+        .map(|r| r.count <= 1)
+        .unwrap_or(true);
+
+    if !is_first_reaction || message.author.bot || message.content.trim().is_empty() {
+        return;
+    }
+
+    if !start_flag_cooldown(user_id) {
+        return;
+    }
+
+    // Languages the guild's provider doesn't support are ignored like any other reaction
+    let translated =
+        match translate_text(ctx, reaction.guild_id, None, target_lang, &message.content).await {
+            Ok(translated) => translated,
+            Err(_) => return,
+        };
+
+    // Messages that are already in the language of the flag don't need a translation
+    if validate_lang_arg(&translated.detection.source_lang) == Some(target_lang) {
+        return;
+    }
+
+    send_translation(
+        ctx,
+        reaction.channel_id,
+        Some(&message),
+        None,
+        target_lang,
+        &translated,
+    )
+    .await;
+}
+
+/// Whether a user had a message translated with a flag reaction too recently
+fn is_on_flag_cooldown(user_id: UserId) -> bool {
+    let mut translations = FLAG_TRANSLATIONS.lock().unwrap();
+    let now = Utc::now();
+
+    // Expired cooldowns are dropped, so only recently active users are kept
+    translations
+        .retain(|_, time| now.signed_duration_since(*time) < Duration::seconds(FLAG_COOLDOWN_SECS));
+
+    translations.contains_key(&user_id.0)
+}
+
+/// Starts the flag translation cooldown of a user, or returns false if it's still running
+fn start_flag_cooldown(user_id: UserId) -> bool {
+    let mut translations = FLAG_TRANSLATIONS.lock().unwrap();
+    let now = Utc::now();
+
+    match translations.get(&user_id.0) {
+        Some(time) if now.signed_duration_since(*time) < Duration::seconds(FLAG_COOLDOWN_SECS) => {
+            false
+        }
+        _ => {
+            translations.insert(user_id.0, now);
+            true
+        }
+    }
+}
+
+#[command("provider")]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[description("Shows or sets the translation provider of this server")]
+#[usage("[google|deepl|libretranslate]")]
+#[example("deepl")]
+async fn translate_provider(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    if !args.is_empty() {
+        let backend = Backend::from_name(args.rest().trim()).ok_or_else(|| {
+            CommandError::from(
+                "Please supply either `google`, `deepl` or `libretranslate` as provider",
+            )
+        })?;
+
+        // Fail early instead of on the next translation
+        backend.provider()?;
+
+        let store = get_store::<TranslateData>(ctx).await;
+        let mut store = store.write().await;
+        store.backends.insert(guild_id.0, backend);
+        store.save();
+    }
+
+    let backend = get_backend(ctx, Some(guild_id)).await;
+    let available: Vec<&str> = Backend::ALL
+        .iter()
+        .filter(|backend| backend.is_configured())
+        .map(|backend| backend.name())
+        .collect();
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description(format!(
+                        "Texts are translated with **{}** on this server",
+                        backend.name()
+                    ))
+                    .footer(|f| f.text(format!("Available: {}", available.join(", "))))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("languages")]
+#[description("Lists the languages the translation provider of this server supports")]
+async fn translate_languages(ctx: &Context, msg: &Message) -> CommandResult {
+    let backend = get_backend(ctx, msg.guild_id).await;
+    let provider = backend.provider()?;
+
+    let languages: Vec<String> = LANGUAGES
+        .iter()
+        .filter_map(|(code, name, _)| {
+            match (provider.source_code(code), provider.target_code(code)) {
+                (Some(_), Some(_)) => Some(format!("{} (`{}`)", name, code)),
+                (None, Some(_)) => Some(format!("{} (`{}`, only as target)", name, code)),
+                (Some(_), None) => Some(format!("{} (`{}`, only as source)", name, code)),
+                (None, None) => None,
+            }
+        })
+        .collect();
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("Languages supported by {}", backend.name()))
+                    .description(languages.join(", "))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+async fn get_backend(ctx: &Context, guild_id: Option<GuildId>) -> Backend {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Backend::default(),
+    };
+
+    let store = get_store::<TranslateData>(ctx).await;
+    let store = store.read().await;
+    store.backends.get(&guild_id.0).copied().unwrap_or_default()
+}
+
+/// A text translated in chunks, along with what the provider detected for the first chunk
+struct TextTranslation {
+    text: String,
+    detection: Translation,
+    backend: Backend,
+}
+
+/// Translates a text with the provider of the guild.
+/// Languages are codes of the language table, the source language is detected if none is given.
+async fn translate_text(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    source_lang: Option<&str>,
+    target_lang: &str,
+    text: &str,
+) -> Result<TextTranslation, CommandError> {
+    let backend = get_backend(ctx, guild_id).await;
+    let provider = backend.provider()?;
+
+    let unsupported = |lang: &str| {
+        CommandError::from(format!(
+            "{} doesn't support {}",
+            backend.name(),
+            get_language_name(lang)
+        ))
+    };
+
+    let provider_target = provider
+        .target_code(target_lang)
+        .ok_or_else(|| unsupported(target_lang))?;
+    let provider_source = match source_lang {
+        Some(lang) => Some(
+            provider
+                .source_code(lang)
+                .ok_or_else(|| unsupported(lang))?,
+        ),
+        None => None,
+    };
+
+    let client = {
+        let data = ctx.data.read().await;
+        data.get::<HttpClientContainer>().unwrap().clone()
+    };
+
+    let mut translation = String::new();
+    let mut detection: Option<Translation> = None;
+
+    for chunk in split_text(text, provider.max_chunk_chars()) {
+        if chunk.trim().is_empty() {
+            translation.push_str(&chunk);
+            continue;
+        }
+
+        // Once detected, the source language is kept, so all chunks are translated alike
+        let chunk_source = provider_source.or_else(|| {
+            detection
+                .as_ref()
+                .and_then(|detection| validate_lang_arg(&detection.source_lang))
+                .and_then(|lang| provider.source_code(lang))
+        });
+
+        let response = provider
+            .translate(&client, chunk_source, provider_target, &chunk)
+            .await?;
+        // Some providers trim the text, which would glue the chunks together
+        let leading_whitespace = &chunk[..chunk.len() - chunk.trim_start().len()];
+        let trailing_whitespace = &chunk[chunk.trim_end().len()..];
+        translation.push_str(leading_whitespace);
+        translation.push_str(response.text.trim());
+        translation.push_str(trailing_whitespace);
+
+        if detection.is_none() {
+            detection = Some(response);
+        }
+    }
+
+    if translation.trim().is_empty() {
+        return Err(CommandError::from("The text couldn't be translated"));
+    }
+
+    Ok(TextTranslation {
+        text: translation,
+        // A text that isn't only whitespace has at least one translated chunk
+        detection: detection.unwrap(),
+        backend,
+    })
+}
+
+/// Sends a translation to a channel, optionally as a reply to the translated message
+async fn send_translation(
+    ctx: &Context,
+    channel_id: ChannelId,
+    reply_to: Option<&Message>,
+    source_lang: Option<&str>,
+    target_lang: &str,
+    translated: &TextTranslation,
+) {
+    let (source_name, footer) = match source_lang {
+        Some(lang) => (get_language_name(lang), None),
+        None => (
+            get_language_name(&translated.detection.source_lang),
+            Some(create_detection_footer(
+                &translated.detection,
+                translated.backend,
+            )),
+        ),
+    };
+
+    let title = format!(
+        "Translation from {} -> {}",
+        source_name,
+        get_language_name(target_lang)
+    );
+
+    // Translations can grow beyond what fits into a single embed
+    let parts = split_text(&translated.text, MAX_EMBED_DESCRIPTION_CHARS);
+    let last_index = parts.len() - 1;
+
+    for (index, part) in parts.into_iter().enumerate() {
+        let _ = channel_id
+            .send_message(&ctx.http, |m| {
+                if let Some(message) = reply_to.filter(|_| index == 0) {
+                    m.reference_message(message)
+                        .allowed_mentions(|a| a.replied_user(false));
+                }
+
+                m.embed(|e| {
+                    e.colour(MAIN_COLOR).description(part);
+
+                    if index == 0 {
+                        e.title(&title);
+                    }
+                    if let Some(footer) = footer.as_ref().filter(|_| index == last_index) {
+                        e.footer(|f| f.text(footer));
+                    }
+
+                    e
+                })
+            })
+            .await;
+    }
+}
+
+fn create_detection_footer(detection: &Translation, backend: Backend) -> String {
+    let mut footer = match detection.confidence {
+        Some(confidence) => format!(
+            "Detected {} with {:.0}% confidence",
+            get_language_name(&detection.source_lang),
+            confidence * 100.0
+        ),
+        None => format!("Detected {}", get_language_name(&detection.source_lang)),
+    };
+
+    let alternatives: Vec<String> = detection
+        .alternatives
+        .iter()
+        .take(MAX_ALTERNATIVES)
+        .map(|(lang, confidence)| {
+            format!("{} ({:.0}%)", get_language_name(lang), confidence * 100.0)
+        })
+        .collect();
+
+    if !alternatives.is_empty() {
+        footer.push_str(&format!("\nAlternatives: {}", alternatives.join(", ")));
+    }
+
+    footer.push_str(&format!(" • {}", backend.name()));
+    footer
+}
+
+/// Splits a text into chunks of at most `max_chars` characters.
+/// Sentences are kept together where possible, otherwise words, and only then single characters.
+fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+
+    for sentence in text.split_inclusive(SENTENCE_ENDS) {
+        if push_piece(&mut chunks, sentence, max_chars) {
+            continue;
+        }
+
+        for word in sentence.split_inclusive(char::is_whitespace) {
+            if push_piece(&mut chunks, word, max_chars) {
+                continue;
+            }
+
+            for character in word.chars() {
+                push_piece(&mut chunks, character.encode_utf8(&mut [0; 4]), max_chars);
+            }
+        }
+    }
+
+    chunks
+}
+
+/// Appends a piece to the last chunk if it still fits, else starts a new chunk with it.
+/// Returns false if the piece alone exceeds the limit.
+fn push_piece(chunks: &mut Vec<String>, piece: &str, max_chars: usize) -> bool {
+    let piece_chars = piece.chars().count();
+    if piece_chars > max_chars {
+        return false;
+    }
+
+    match chunks.last_mut() {
+        Some(chunk) if chunk.chars().count() + piece_chars <= max_chars => chunk.push_str(piece),
+        _ => chunks.push(piece.to_string()),
+    }
+
+    true
+}
+
+/// Language code, name and further aliases of the languages the translation supports.
+/// The codes are Google's, other providers map them to their own and not all of them support every language.
+const LANGUAGES: &[(&str, &str, &[&str])] = &[
+    ("af", "Afrikaans", &[]),
+    ("sq", "Albanian", &[]),
+    ("am", "Amharic", &[]),
+    ("ar", "Arabic", &[]),
+    ("hy", "Armenian", &[]),
+    ("az", "Azerbaijani", &[]),
+    ("eu", "Basque", &[]),
+    ("be", "Belarusian", &[]),
+    ("bn", "Bengali", &[]),
+    ("bs", "Bosnian", &[]),
+    ("bg", "Bulgarian", &[]),
+    ("ca", "Catalan", &[]),
+    ("ceb", "Cebuano", &[]),
+    ("zh", "Chinese", &["zh-cn"]),
+    ("zh-TW", "Chinese (Traditional)", &["zt"]),
+    ("co", "Corsican", &[]),
+    ("hr", "Croatian", &[]),
+    ("cs", "Czech", &[]),
+    ("da", "Danish", &[]),
+    ("nl", "Dutch", &[]),
+    ("en", "English", &[]),
+    ("eo", "Esperanto", &[]),
+    ("et", "Estonian", &[]),
+    ("fi", "Finnish", &[]),
+    ("fr", "French", &[]),
+    ("fy", "Frisian", &[]),
+    ("gl", "Galician", &[]),
+    ("ka", "Georgian", &[]),
+    ("de", "German", &[]),
+    ("el", "Greek", &[]),
+    ("gu", "Gujarati", &[]),
+    ("ht", "Haitian Creole", &["haitian", "creole"]),
+    ("ha", "Hausa", &[]),
+    ("haw", "Hawaiian", &[]),
+    ("iw", "Hebrew", &["he"]),
+    ("hi", "Hindi", &[]),
+    ("hmn", "Hmong", &[]),
+    ("hu", "Hungarian", &[]),
+    ("is", "Icelandic", &[]),
+    ("ig", "Igbo", &[]),
+    ("id", "Indonesian", &[]),
+    ("ga", "Irish", &[]),
+    ("it", "Italian", &[]),
+    ("ja", "Japanese", &["jp"]),
+    ("jv", "Javanese", &[]),
+    ("kn", "Kannada", &[]),
+    ("kk", "Kazakh", &[]),
+    ("km", "Khmer", &[]),
+    ("rw", "Kinyarwanda", &[]),
+    ("ko", "Korean", &[]),
+    ("ku", "Kurdish", &[]),
+    ("ky", "Kyrgyz", &[]),
+    ("lo", "Lao", &[]),
+    ("la", "Latin", &[]),
+    ("lv", "Latvian", &[]),
+    ("lt", "Lithuanian", &[]),
+    ("lb", "Luxembourgish", &[]),
+    ("mk", "Macedonian", &[]),
+    ("mg", "Malagasy", &[]),
+    ("ms", "Malay", &[]),
+    ("ml", "Malayalam", &[]),
+    ("mt", "Maltese", &[]),
+    ("mi", "Maori", &[]),
+    ("mr", "Marathi", &[]),
+    ("mn", "Mongolian", &[]),
+    ("my", "Myanmar", &[]),
+    ("ne", "Nepali", &[]),
+    ("no", "Norwegian", &["nb"]),
+    ("ny", "Nyanja", &[]),
+    ("or", "Odia", &[]),
+    ("ps", "Pashto", &[]),
+    ("fa", "Persian", &[]),
+    ("pl", "Polish", &[]),
+    ("pt", "Portuguese", &[]),
+    ("pa", "Punjabi", &[]),
+    ("ro", "Romanian", &[]),
+    ("ru", "Russian", &[]),
+    ("sm", "Samoan", &[]),
+    ("gd", "Scots Gaelic", &["scots", "gaelic"]),
+    ("sr", "Serbian", &[]),
+    ("st", "Sesotho", &[]),
+    ("sn", "Shona", &[]),
+    ("sd", "Sindhi", &[]),
+    ("si", "Sinhala", &[]),
+    ("sk", "Slovak", &[]),
+    ("sl", "Slovenian", &[]),
+    ("so", "Somali", &[]),
+    ("es", "Spanish", &[]),
+    ("su", "Sundanese", &[]),
+    ("sw", "Swahili", &[]),
+    ("sv", "Swedish", &[]),
+    ("tl", "Tagalog", &[]),
+    ("tg", "Tajik", &[]),
+    ("ta", "Tamil", &[]),
+    ("tt", "Tatar", &[]),
+    ("te", "Telugu", &[]),
+    ("th", "Thai", &[]),
+    ("tr", "Turkish", &[]),
+    ("tk", "Turkmen", &[]),
+    ("uk", "Ukrainian", &[]),
+    ("ur", "Urdu", &[]),
+    ("ug", "Uyghur", &[]),
+    ("uz", "Uzbek", &[]),
+    ("vi", "Vietnamese", &[]),
+    ("cy", "Welsh", &[]),
+    ("xh", "Xhosa", &[]),
+    ("yi", "Yiddish", &[]),
+    ("yo", "Yoruba", &[]),
+    ("zu", "Zulu", &[]),
+];
+
+/// Finds a language by its code, name or alias and returns its code and name
+fn find_language(lang_arg: &str) -> Option<(&'static str, &'static str)> {
+    let arg_lowercase = lang_arg.to_ascii_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|(code, name, aliases)| {
+            code.eq_ignore_ascii_case(&arg_lowercase)
+                || name.eq_ignore_ascii_case(&arg_lowercase)
+                || aliases.contains(&arg_lowercase.as_str())
+        })
+        .map(|(code, name, _)| (*code, *name))
+}
+
+fn validate_lang_arg(lang_arg: &str) -> Option<&'static str> {
+    find_language(lang_arg).map(|(code, _)| code)
+}
+
+/// Returns the name of a language code, or the code itself for unknown languages
+fn get_language_name(code: &str) -> String {
+    match find_language(code) {
+        Some((_, name)) => name.to_string(),
+        None => code.to_ascii_uppercase(),
+    }
+}
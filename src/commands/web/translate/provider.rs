@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use serenity::{async_trait, framework::standard::CommandError};
+use std::env;
+
+use super::{
+    deepl::{DeepL, DEEPL_API_KEY_VAR},
+    google::Google,
+    libretranslate::{LibreTranslate, LIBRETRANSLATE_URL_VAR},
+};
+
+/// A translated text along with the language it was translated from
+pub struct Translation {
+    pub text: String,
+    // Source language as returned by the provider
+    pub source_lang: String,
+    pub confidence: Option<f64>,
+    // Other languages the text might be in, with their confidence
+    pub alternatives: Vec<(String, f64)>,
+}
+
+/// A translation service
+#[async_trait]
+pub trait TranslationProvider: Send + Sync {
+    /// Longest text that is sent in a single request
+    fn max_chunk_chars(&self) -> usize;
+
+    /// Maps a code of the language table to the provider's code of a source language,
+    /// or None if the provider can't translate from that language
+    fn source_code(&self, lang: &str) -> Option<&'static str>;
+
+    /// Maps a code of the language table to the provider's code of a target language,
+    /// or None if the provider can't translate into that language
+    fn target_code(&self, lang: &str) -> Option<&'static str>;
+
+    /// Translates a text, detecting its language if no source language is given
+    async fn translate(
+        &self,
+        client: &reqwest::Client,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<Translation, CommandError>;
+}
+
+/// The translation backends a guild can choose from
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Google,
+    DeepL,
+    LibreTranslate,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Google, Backend::DeepL, Backend::LibreTranslate];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "google" => Some(Backend::Google),
+            "deepl" => Some(Backend::DeepL),
+            "libretranslate" | "libre" => Some(Backend::LibreTranslate),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Google => "Google",
+            Backend::DeepL => "DeepL",
+            Backend::LibreTranslate => "LibreTranslate",
+        }
+    }
+
+    /// Whether the bot owner set up what the backend needs
+    pub fn is_configured(self) -> bool {
+        match self {
+            Backend::Google => true,
+            Backend::DeepL => env::var(DEEPL_API_KEY_VAR).is_ok(),
+            Backend::LibreTranslate => env::var(LIBRETRANSLATE_URL_VAR).is_ok(),
+        }
+    }
+
+    pub fn provider(self) -> Result<Box<dyn TranslationProvider>, CommandError> {
+        if !self.is_configured() {
+            return Err(CommandError::from(format!(
+                "The bot owner didn't set up {}",
+                self.name()
+            )));
+        }
+
+        Ok(match self {
+            Backend::Google => Box::new(Google),
+            Backend::DeepL => Box::new(DeepL::from_env()?),
+            Backend::LibreTranslate => Box::new(LibreTranslate::from_env()?),
+        })
+    }
+}
+
+/// Looks up a code of the language table in a list of (table code, provider code) pairs
+pub fn map_language(languages: &[(&str, &'static str)], lang: &str) -> Option<&'static str> {
+    languages
+        .iter()
+        .find(|(table_code, _)| table_code.eq_ignore_ascii_case(lang))
+        .map(|(_, provider_code)| *provider_code)
+}
@@ -0,0 +1,139 @@
+use plotters::{
+    prelude::*,
+    style::{register_font, FontStyle},
+};
+use std::{error::Error, iter, sync::Once};
+
+const CHART_WIDTH: u32 = 800;
+const CHART_HEIGHT: u32 = 360;
+// Colors of discord's dark theme, so the chart blends into the embed
+const BACKGROUND_COLOR: RGBColor = RGBColor(47, 49, 54);
+const TEXT_COLOR: RGBColor = RGBColor(220, 221, 222);
+const GRID_COLOR: RGBColor = RGBColor(79, 84, 92);
+const TEMP_COLOR: RGBColor = RGBColor(250, 166, 26);
+const TEMP_MIN_COLOR: RGBColor = RGBColor(114, 137, 218);
+const PRECIPITATION_COLOR: RGBColor = RGBColor(88, 101, 242);
+
+// The runtime image has no system fonts, so the font is embedded into the binary
+static FONT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/assets/fonts/DejaVuSans.ttf"
+));
+static REGISTER_FONT: Once = Once::new();
+
+/// A point in time of a forecast chart, with temperatures already in the shown unit
+pub struct ChartPoint {
+    pub label: String,
+    pub temp: f64,
+    // Daily forecasts also show the minimum temperature of the day
+    pub temp_min: Option<f64>,
+    // Probability of precipitation from 0 to 1
+    pub pop: f64,
+}
+
+/// Renders the temperatures as lines and the probability of precipitation as bars into a png
+pub fn render_chart(
+    points: &[ChartPoint],
+    temp_symbol: &str,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    if points.is_empty() {
+        return Err("There are no points to chart".into());
+    }
+
+    REGISTER_FONT.call_once(|| {
+        let _ = register_font("sans-serif", FontStyle::Normal, FONT);
+    });
+
+    let mut pixels = vec![0; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (CHART_WIDTH, CHART_HEIGHT))
+            .into_drawing_area();
+        root.fill(&BACKGROUND_COLOR)?;
+
+        let (temp_min, temp_max) = points
+            .iter()
+            .flat_map(|p| iter::once(p.temp).chain(p.temp_min))
+            .fold((f64::MAX, f64::MIN), |(min, max), t| {
+                (min.min(t), max.max(t))
+            });
+        // Leave some room above and below the lines
+        let temp_range = (temp_min - 2.0).floor()..(temp_max + 2.0).ceil();
+        let x_range = -0.5..(points.len() as f64 - 0.5);
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(15)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .right_y_label_area_size(50)
+            .build_cartesian_2d(x_range.clone(), temp_range)?
+            .set_secondary_coord(x_range, 0.0..100.0);
+
+        let label_style = ("sans-serif", 14).into_font().color(&TEXT_COLOR);
+        let label_formatter = |x: &f64| {
+            points
+                .get(x.round() as usize)
+                .filter(|_| *x >= 0.0 && (x - x.round()).abs() < 0.01)
+                .map(|p| p.label.clone())
+                .unwrap_or_default()
+        };
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .set_tick_mark_size(LabelAreaPosition::Bottom, 0)
+            .bold_line_style(GRID_COLOR)
+            .y_max_light_lines(1)
+            .light_line_style(GRID_COLOR.mix(0.3))
+            .axis_style(GRID_COLOR)
+            .x_labels(points.len())
+            .x_label_formatter(&label_formatter)
+            .y_label_formatter(&|y| format!("{:.0}{}", y, temp_symbol))
+            .label_style(label_style.clone())
+            .draw()?;
+
+        chart
+            .configure_secondary_axes()
+            .axis_style(GRID_COLOR)
+            .y_label_formatter(&|y| format!("{:.0}%", y))
+            .label_style(label_style)
+            .draw()?;
+
+        chart.draw_secondary_series(points.iter().enumerate().map(|(i, p)| {
+            let x = i as f64;
+            Rectangle::new(
+                [(x - 0.3, 0.0), (x + 0.3, p.pop * 100.0)],
+                PRECIPITATION_COLOR.mix(0.5).filled(),
+            )
+        }))?;
+
+        let min_points: Vec<(f64, f64)> = points
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.temp_min.map(|t| (i as f64, t)))
+            .collect();
+        let temp_points: Vec<(f64, f64)> = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i as f64, p.temp))
+            .collect();
+
+        for (line, color) in [(min_points, TEMP_MIN_COLOR), (temp_points, TEMP_COLOR)] {
+            chart.draw_series(LineSeries::new(line.clone(), color.stroke_width(3)))?;
+            chart.draw_series(
+                line.into_iter()
+                    .map(|point| Circle::new(point, 4, color.filled())),
+            )?;
+        }
+
+        root.present()?;
+    }
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, CHART_WIDTH, CHART_HEIGHT);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    Ok(png)
+}
@@ -0,0 +1,1213 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    builder::CreateEmbed,
+    framework::standard::{macros::command, Args, CommandError, CommandResult},
+    http::AttachmentType,
+    model::{
+        channel::{Message, ReactionType},
+        id::{ChannelId, UserId},
+    },
+    prelude::{Context, SerenityError},
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+mod chart;
+mod openmeteo;
+mod openweathermap;
+mod provider;
+
+use self::chart::{render_chart, ChartPoint};
+use self::provider::get_provider;
+use super::convert::{
+    convert_distance, convert_temperature, convert_velocity, DistanceType, TemperatureType,
+    VelocityType,
+};
+use crate::core::{
+    checks::ADMIN_CHECK,
+    constants::MAIN_COLOR,
+    scheduler::{cancel, get_tasks, schedule, ActionExecutor, ScheduledAction, ScheduledTask},
+    storage::get_store,
+    util::{reaction_matches, uppercase_first},
+};
+
+const IMPERIAL_FLAG: &str = "--imperial";
+const METRIC_FLAG: &str = "--metric";
+const HPA_TO_INHG: f64 = 0.02953;
+const HOURLY_FORECAST_HOURS: usize = 12;
+const HOURLY_CHART_HOURS: usize = 24;
+const HOURLY_CHART_LABEL_INTERVAL: usize = 3;
+const CHART_FILE_NAME: &str = "forecast.png";
+const MAX_SHOWN_ALERTS: usize = 3;
+// Alert texts are often several paragraphs long, embeds only show their beginning
+const MAX_ALERT_DESCRIPTION_CHARS: usize = 300;
+const CHOICE_EMOJIS: [&str; 5] = ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣"];
+const CHOICE_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct WeatherData {
+    // Preferred units by user id
+    units: HashMap<u64, Units>,
+    // Home locations by user id
+    homes: HashMap<u64, Location>,
+    // Daily forecast subscriptions by channel id
+    forecasts: HashMap<u64, ForecastSubscription>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Location {
+    name: String,
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ForecastSubscription {
+    guild_id: u64,
+    location: Location,
+    // Local time of the location
+    time: NaiveTime,
+    units: Units,
+    // Last known UTC offset of the location in seconds, as returned by the api
+    timezone_offset: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "metric" => Some(Units::Metric),
+            "imperial" => Some(Units::Imperial),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        }
+    }
+
+    // The api is always queried in metric units, imperial values are converted locally
+    fn temp_value(self, celsius: f64) -> f64 {
+        match self {
+            Units::Metric => celsius,
+            Units::Imperial => convert_temperature(
+                celsius,
+                TemperatureType::CELSIUS,
+                TemperatureType::FAHRENHEIT,
+            ),
+        }
+    }
+
+    fn temp_symbol(self) -> &'static str {
+        match self {
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+        }
+    }
+
+    fn format_temp(self, celsius: f64) -> String {
+        format!("{:.0}{}", self.temp_value(celsius), self.temp_symbol())
+    }
+
+    fn format_speed(self, meters_per_second: f64) -> String {
+        match self {
+            Units::Metric => format!("{:.1} m/s", meters_per_second),
+            Units::Imperial => format!(
+                "{:.1} mph",
+                convert_velocity(
+                    meters_per_second,
+                    VelocityType::METERSPERSECOND,
+                    VelocityType::MILESPERHOUR
+                )
+            ),
+        }
+    }
+
+    fn format_distance(self, meters: i32) -> String {
+        match self {
+            Units::Metric => format!("{:.1} km", meters as f64 / 1000.0),
+            Units::Imperial => format!(
+                "{:.1} mi",
+                convert_distance(meters as f64, DistanceType::METER, DistanceType::MILE)
+            ),
+        }
+    }
+
+    fn format_precipitation(self, millimeters: f64) -> String {
+        match self {
+            Units::Metric => format!("{:.1} mm", millimeters),
+            Units::Imperial => format!(
+                "{:.2} in",
+                convert_distance(millimeters, DistanceType::MILLIMETER, DistanceType::INCH)
+            ),
+        }
+    }
+
+    fn format_pressure(self, hpa: i32) -> String {
+        match self {
+            Units::Metric => format!("{} hPa", hpa),
+            Units::Imperial => format!("{:.2} inHg", hpa as f64 * HPA_TO_INHG),
+        }
+    }
+}
+
+#[command]
+#[sub_commands(weather_hourly, weather_daily, weather_set, weather_units)]
+#[description(
+    "Retrieves the weather forecast at the given location, which can be a city name, \
+    a postal code or coordinates. Without a location your home location is used. \
+    Add `--imperial` or `--metric` to override your preferred units."
+)]
+#[usage("[location] [--imperial|--metric]")]
+#[example("Berlin")]
+#[example("Sri Lanka")]
+#[example("New York --imperial")]
+#[example("10001")]
+#[example("52.52, 13.40")]
+pub async fn weather(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let (search_arg, units) = parse_weather_args(ctx, msg, &args).await?;
+    let location = resolve_location(ctx, msg, &search_arg).await?;
+    let weather = fetch_weather(ctx, &location).await?;
+    let current = &weather.current;
+
+    let mut details = format!(
+        "**Clouds**: {}% \n\
+        **Humidity**: {}% \n\
+        **Pressure**: {}\n\
+        **UV Index**: {:.1}",
+        current.clouds,
+        current.humidity,
+        units.format_pressure(current.pressure),
+        current.uvi
+    );
+    if let Some(visibility) = current.visibility {
+        details.push_str(&format!(
+            "\n**Visibility**: {}",
+            units.format_distance(visibility)
+        ));
+    }
+
+    let mut fields = vec![
+        ("Weather".to_string(), details, true),
+        (
+            "Wind".to_string(),
+            format!(
+                "**Speed**: {}\n\
+                **Direction**: {}° ({})",
+                units.format_speed(current.wind_speed),
+                current.wind_deg,
+                format_direction(current.wind_deg)
+            ),
+            true,
+        ),
+    ];
+
+    let precipitation = format_precipitation(&weather, units);
+    if !precipitation.is_empty() {
+        fields.push(("Precipitation".to_string(), precipitation, true));
+    }
+
+    fields.push((
+        "Location".to_string(),
+        format!(
+            "**Sunrise**: {}\n\
+            **Sunset**: {}\n\
+            **Local Time**: {}",
+            format_timestamp(current.sunrise, weather.timezone_offset, "%H:%M"),
+            format_timestamp(current.sunset, weather.timezone_offset, "%H:%M"),
+            format_timestamp(current.dt, weather.timezone_offset, "%H:%M, %b %e %Y"),
+        ),
+        false,
+    ));
+
+    for alert in weather.alerts.iter().take(MAX_SHOWN_ALERTS) {
+        fields.push((
+            format!("⚠️ {}", alert.event),
+            format!(
+                "**{}**, from {} until {}\n{}",
+                alert.sender_name,
+                format_timestamp(alert.start, weather.timezone_offset, "%H:%M, %b %e"),
+                format_timestamp(alert.end, weather.timezone_offset, "%H:%M, %b %e"),
+                truncate(&alert.description, MAX_ALERT_DESCRIPTION_CHARS)
+            ),
+            false,
+        ));
+    }
+
+    let (emoji, condition) = describe_condition(&current.weather);
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("Weather in {}", location.name))
+                    .description(format!(
+                        "{} **{}** \n\
+                        **Temp**: {} (Feels like {})",
+                        emoji,
+                        condition,
+                        units.format_temp(current.temp),
+                        units.format_temp(current.feels_like)
+                    ))
+                    .fields(fields);
+                if let Some(weather) = current.weather.first() {
+                    e.thumbnail(get_weather_image_url(&weather.icon));
+                }
+                e
+            })
+        })
+        .await;
+
+    // The first day is today, which the current weather already covers
+    let days = weather.daily.get(1..).unwrap_or_default();
+    if days.is_empty() {
+        return Ok(());
+    }
+    let description = days
+        .iter()
+        .map(|day| {
+            let (emoji, condition) = describe_condition(&day.weather);
+            format!(
+                "`{}` {} {}, {}% humidity",
+                format_timestamp(day.dt, weather.timezone_offset, "%a %e %b"),
+                emoji,
+                condition,
+                day.humidity
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let points: Vec<ChartPoint> = days
+        .iter()
+        .map(|day| ChartPoint {
+            label: format_timestamp(day.dt, weather.timezone_offset, "%a %e"),
+            temp: units.temp_value(day.temp.max),
+            temp_min: Some(units.temp_value(day.temp.min)),
+            pop: day.pop,
+        })
+        .collect();
+
+    send_chart_embed(
+        ctx,
+        msg.channel_id,
+        format!("Forecast for {}", location.name),
+        description,
+        &points,
+        units,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command("hourly")]
+#[description("Retrieves the hourly weather forecast at the given location")]
+#[usage("[location] [--imperial|--metric]")]
+#[example("Berlin")]
+#[example("New York --imperial")]
+async fn weather_hourly(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let (search_arg, units) = parse_weather_args(ctx, msg, &args).await?;
+    let location = resolve_location(ctx, msg, &search_arg).await?;
+    let weather = fetch_weather(ctx, &location).await?;
+    if weather.hourly.is_empty() {
+        return Err(CommandError::from(
+            "There's no hourly forecast for this location",
+        ));
+    }
+
+    let description = weather
+        .hourly
+        .iter()
+        .take(HOURLY_FORECAST_HOURS)
+        .map(|hour| {
+            let mut line = format!(
+                "`{}` {} **{}**, {}, {:.0}% precipitation",
+                format_timestamp(hour.dt, weather.timezone_offset, "%H:%M"),
+                describe_condition(&hour.weather).0,
+                units.format_temp(hour.temp),
+                units.format_speed(hour.wind_speed),
+                hour.pop * 100.0
+            );
+            if let Some(volume) = hour.rain.as_ref().or(hour.snow.as_ref()) {
+                line.push_str(&format!(
+                    " ({})",
+                    units.format_precipitation(volume.one_hour)
+                ));
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // The chart covers a longer time than the listed hours, with a label every few hours
+    let points: Vec<ChartPoint> = weather
+        .hourly
+        .iter()
+        .take(HOURLY_CHART_HOURS)
+        .enumerate()
+        .map(|(i, hour)| ChartPoint {
+            label: match i % HOURLY_CHART_LABEL_INTERVAL {
+                0 => format_timestamp(hour.dt, weather.timezone_offset, "%H:%M"),
+                _ => String::new(),
+            },
+            temp: units.temp_value(hour.temp),
+            temp_min: None,
+            pop: hour.pop,
+        })
+        .collect();
+
+    send_chart_embed(
+        ctx,
+        msg.channel_id,
+        format!("Hourly forecast for {}", location.name),
+        description,
+        &points,
+        units,
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command("daily")]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[sub_commands(weather_daily_off)]
+#[description(
+    "Posts a forecast to a channel every day at the given local time of the location. \
+    Without arguments, the daily forecasts of this server are shown."
+)]
+#[usage("[#channel <time> <location> [--imperial|--metric]]")]
+#[example("#general 07:00 Berlin")]
+#[example("#weather 06:30 New York --imperial")]
+async fn weather_daily(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    if args.is_empty() {
+        return list_forecasts(ctx, msg).await;
+    }
+
+    let invalid_channel = || CommandError::from("Please supply a channel of this server");
+    let channel_id = args.single::<ChannelId>().map_err(|_| invalid_channel())?;
+    if ctx
+        .cache
+        .guild_channel_field(channel_id, |c| c.guild_id)
+        .await
+        != Some(guild_id)
+    {
+        return Err(invalid_channel());
+    }
+
+    let time = args
+        .single::<String>()
+        .ok()
+        .and_then(|t| NaiveTime::parse_from_str(&t, "%H:%M").ok())
+        .ok_or_else(|| CommandError::from("Please supply a valid time, i.e. `07:00`"))?;
+
+    let (search_arg, units) = parse_weather_args(ctx, msg, &args).await?;
+    let location = resolve_location(ctx, msg, &search_arg).await?;
+    // The location's current UTC offset is needed to schedule the first forecast
+    let weather = fetch_weather(ctx, &location).await?;
+
+    {
+        let store = get_store::<WeatherData>(ctx).await;
+        let mut store = store.write().await;
+        store.forecasts.insert(
+            channel_id.0,
+            ForecastSubscription {
+                guild_id: guild_id.0,
+                location: location.clone(),
+                time,
+                units,
+                timezone_offset: weather.timezone_offset,
+            },
+        );
+        store.save();
+    }
+
+    cancel_forecast(ctx, channel_id).await;
+    schedule(
+        ctx,
+        next_forecast_time(time, weather.timezone_offset),
+        ScheduledAction::WeatherForecast {
+            channel_id: channel_id.0,
+        },
+    )
+    .await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "The forecast for **{}** is posted to <#{}> every day at {} local time",
+                    location.name,
+                    channel_id.0,
+                    time.format("%H:%M")
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("off")]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[description("Stops posting daily forecasts to a channel")]
+#[usage("<#channel>")]
+#[example("#general")]
+#[min_args(1)]
+async fn weather_daily_off(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let channel_id = args
+        .single::<ChannelId>()
+        .map_err(|_| CommandError::from("Please supply a valid channel mention or id"))?;
+
+    {
+        let store = get_store::<WeatherData>(ctx).await;
+        let mut store = store.write().await;
+
+        match store.forecasts.get(&channel_id.0) {
+            Some(subscription) if subscription.guild_id == guild_id.0 => {
+                store.forecasts.remove(&channel_id.0);
+                store.save();
+            }
+            _ => {
+                return Err(CommandError::from(format!(
+                    "No daily forecast is posted to <#{}>",
+                    channel_id.0
+                )))
+            }
+        }
+    }
+
+    cancel_forecast(ctx, channel_id).await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Daily forecasts aren't posted to <#{}> anymore",
+                    channel_id.0
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("set")]
+#[description("Saves your home location, which is used when you don't supply a location")]
+#[usage("<location>")]
+#[example("Springfield")]
+#[example("10001")]
+#[example("52.52, 13.40")]
+#[min_args(1)]
+async fn weather_set(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let location = resolve_location(ctx, msg, args.rest().trim()).await?;
+
+    {
+        let store = get_store::<WeatherData>(ctx).await;
+        let mut store = store.write().await;
+        store.homes.insert(msg.author.id.0, location.clone());
+        store.save();
+    }
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .description(format!("Your home location is now **{}**", location.name))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+#[command("units")]
+#[description("Shows or sets the units that weather is shown in for you")]
+#[usage("[metric|imperial]")]
+#[example("imperial")]
+async fn weather_units(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !args.is_empty() {
+        let units = Units::from_name(args.rest().trim()).ok_or_else(|| {
+            CommandError::from("Please supply either `metric` or `imperial` as units")
+        })?;
+
+        let store = get_store::<WeatherData>(ctx).await;
+        let mut store = store.write().await;
+        store.units.insert(msg.author.id.0, units);
+        store.save();
+    }
+
+    let units = get_units(ctx, msg.author.id).await;
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).description(format!(
+                    "Weather is shown to you in **{}** units",
+                    units.name()
+                ))
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Posts daily forecasts once they're due
+pub struct ForecastExecutor;
+
+#[async_trait]
+impl ActionExecutor for ForecastExecutor {
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError> {
+        let channel_id = match action {
+            ScheduledAction::WeatherForecast { channel_id } => ChannelId(*channel_id),
+            _ => return Ok(()),
+        };
+
+        // Failed forecasts aren't retried, as the next one has been scheduled already
+        if let Err(why) = deliver_forecast(ctx, channel_id).await {
+            error!(
+                "Couldn't post the daily forecast to channel {}: {:?}",
+                channel_id, why
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Posts the daily forecast to a subscribed channel and schedules the next one
+async fn deliver_forecast(ctx: &Context, channel_id: ChannelId) -> Result<(), SerenityError> {
+    let subscription = {
+        let store = get_store::<WeatherData>(ctx).await;
+        let store = store.read().await;
+
+        match store.forecasts.get(&channel_id.0) {
+            Some(subscription) => subscription.clone(),
+            None => return Ok(()),
+        }
+    };
+
+    let result = fetch_weather(ctx, &subscription.location).await;
+
+    // The next forecast is scheduled even if this one fails, so the subscription doesn't end.
+    // The offset changes with daylight saving time, so the latest one is kept.
+    let timezone_offset = match &result {
+        Ok(weather) => weather.timezone_offset,
+        Err(_) => subscription.timezone_offset,
+    };
+    if timezone_offset != subscription.timezone_offset {
+        let store = get_store::<WeatherData>(ctx).await;
+        let mut store = store.write().await;
+
+        if let Some(subscription) = store.forecasts.get_mut(&channel_id.0) {
+            subscription.timezone_offset = timezone_offset;
+            store.save();
+        }
+    }
+    schedule(
+        ctx,
+        next_forecast_time(subscription.time, timezone_offset),
+        ScheduledAction::WeatherForecast {
+            channel_id: channel_id.0,
+        },
+    )
+    .await;
+
+    let weather = match result {
+        Ok(weather) => weather,
+        Err(why) => {
+            error!(
+                "Couldn't get the daily forecast for channel {}: {:?}",
+                channel_id, why
+            );
+            return Ok(());
+        }
+    };
+
+    let embed = match create_daily_embed(&subscription.location, &weather, subscription.units) {
+        Some(embed) => embed,
+        None => {
+            error!("The daily forecast for channel {} has no days", channel_id);
+            return Ok(());
+        }
+    };
+    channel_id
+        .send_message(&ctx.http, |m| m.set_embed(embed))
+        .await?;
+
+    Ok(())
+}
+
+async fn list_forecasts(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let description = {
+        let store = get_store::<WeatherData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .forecasts
+            .iter()
+            .filter(|(_, subscription)| subscription.guild_id == guild_id.0)
+            .map(|(channel_id, subscription)| {
+                format!(
+                    "<#{}> at {} for **{}** ({})",
+                    channel_id,
+                    subscription.time.format("%H:%M"),
+                    subscription.location.name,
+                    subscription.units.name()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR).title("Daily forecasts").description(
+                    match description.is_empty() {
+                        true => "No daily forecasts are posted in this server".to_string(),
+                        false => description,
+                    },
+                )
+            })
+        })
+        .await;
+
+    Ok(())
+}
+
+async fn cancel_forecast(ctx: &Context, channel_id: ChannelId) {
+    let is_forecast = |task: &ScheduledTask| matches!(task.action, ScheduledAction::WeatherForecast { channel_id: id } if id == channel_id.0);
+
+    for task in get_tasks(ctx, is_forecast).await {
+        cancel(ctx, task.id, is_forecast).await;
+    }
+}
+
+/// Returns the next time it's the given time of day at the location
+fn next_forecast_time(time: NaiveTime, timezone_offset: i32) -> DateTime<Utc> {
+    let timezone = FixedOffset::east(timezone_offset);
+    let now = Utc::now().with_timezone(&timezone);
+
+    let date = match time > now.time() {
+        true => now.date().naive_local(),
+        false => now.date().naive_local().succ(),
+    };
+
+    timezone
+        .from_local_datetime(&date.and_time(time))
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn create_daily_embed(
+    location: &Location,
+    weather: &WeatherReport,
+    units: Units,
+) -> Option<CreateEmbed> {
+    let today = weather.daily.first()?;
+    let (emoji, condition) = describe_condition(&today.weather);
+    let mut e = CreateEmbed::default();
+
+    let volume = today.rain.unwrap_or_default() + today.snow.unwrap_or_default();
+    let precipitation = match volume > 0.0 {
+        true => format!(
+            "{:.0}% ({})",
+            today.pop * 100.0,
+            units.format_precipitation(volume)
+        ),
+        false => format!("{:.0}%", today.pop * 100.0),
+    };
+
+    e.colour(MAIN_COLOR)
+        .title(format!("Today's forecast for {}", location.name))
+        .description(format!(
+            "{} **{}** \n\
+            **Temp**: {} to {}\n\
+            **Precipitation**: {}\n\
+            **Wind**: {}\n\
+            **UV Index**: {:.1}\n\
+            **Sunrise**: {}, **Sunset**: {}",
+            emoji,
+            condition,
+            units.format_temp(today.temp.min),
+            units.format_temp(today.temp.max),
+            precipitation,
+            units.format_speed(today.wind_speed),
+            today.uvi,
+            format_timestamp(today.sunrise, weather.timezone_offset, "%H:%M"),
+            format_timestamp(today.sunset, weather.timezone_offset, "%H:%M"),
+        ));
+    if let Some(weather) = today.weather.first() {
+        e.thumbnail(get_weather_image_url(&weather.icon));
+    }
+
+    for alert in weather.alerts.iter().take(MAX_SHOWN_ALERTS) {
+        e.field(
+            format!("⚠️ {}", alert.event),
+            truncate(&alert.description, MAX_ALERT_DESCRIPTION_CHARS),
+            false,
+        );
+    }
+
+    Some(e)
+}
+
+/// Sends an embed with a chart of the forecast. If the chart can't be rendered,
+/// the embed is sent without it.
+async fn send_chart_embed(
+    ctx: &Context,
+    channel_id: ChannelId,
+    title: String,
+    description: String,
+    points: &[ChartPoint],
+    units: Units,
+) {
+    let mut embed = CreateEmbed::default();
+    embed
+        .colour(MAIN_COLOR)
+        .title(title)
+        .description(description);
+
+    let chart = match render_chart(points, units.temp_symbol()) {
+        Ok(chart) => chart,
+        Err(why) => {
+            error!("Couldn't render the weather chart: {:?}", why);
+            let _ = channel_id
+                .send_message(&ctx.http, |m| m.set_embed(embed))
+                .await;
+            return;
+        }
+    };
+
+    embed.image(format!("attachment://{}", CHART_FILE_NAME));
+    let attachment = AttachmentType::Bytes {
+        data: Cow::from(chart),
+        filename: CHART_FILE_NAME.to_string(),
+    };
+
+    let _ = channel_id
+        .send_files(&ctx.http, vec![attachment], |m| m.set_embed(embed))
+        .await;
+}
+
+async fn get_units(ctx: &Context, user_id: UserId) -> Units {
+    let store = get_store::<WeatherData>(ctx).await;
+    let store = store.read().await;
+
+    store.units.get(&user_id.0).copied().unwrap_or_default()
+}
+
+/// Splits the unit flags off the arguments and falls back to the user's preferred units
+async fn parse_weather_args(
+    ctx: &Context,
+    msg: &Message,
+    args: &Args,
+) -> Result<(String, Units), CommandError> {
+    let mut words: Vec<&str> = args.rest().split(' ').collect();
+    let units = match (words.contains(&IMPERIAL_FLAG), words.contains(&METRIC_FLAG)) {
+        (true, true) => {
+            return Err(CommandError::from(
+                "Please use either `--imperial` or `--metric`",
+            ))
+        }
+        (true, false) => Units::Imperial,
+        (false, true) => Units::Metric,
+        (false, false) => get_units(ctx, msg.author.id).await,
+    };
+    words.retain(|w| *w != IMPERIAL_FLAG && *w != METRIC_FLAG);
+
+    let search_arg = words.join(" ").trim().to_string();
+
+    Ok((search_arg, units))
+}
+
+/// Resolves coordinates, a postal code or a place name to a location.
+/// Falls back to the user's home location if no location is given.
+async fn resolve_location(
+    ctx: &Context,
+    msg: &Message,
+    search_arg: &str,
+) -> Result<Location, CommandError> {
+    if search_arg.is_empty() {
+        let store = get_store::<WeatherData>(ctx).await;
+        let store = store.read().await;
+
+        return store.homes.get(&msg.author.id.0).cloned().ok_or_else(|| {
+            CommandError::from(
+                "Please supply a location, or save your home location with `weather set`",
+            )
+        });
+    }
+
+    if let Some((lat, lon)) = parse_coordinates(search_arg) {
+        return Ok(Location {
+            name: format!("{:.2}, {:.2}", lat, lon),
+            lat,
+            lon,
+        });
+    }
+
+    let provider = get_provider();
+
+    // Differently spelled searches for the same place share their cache entries
+    let normalized_search = search_arg
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    // Postal codes can optionally be followed by a country code, i.e. "E14,GB"
+    if search_arg.chars().any(|c| c.is_ascii_digit()) {
+        if let Ok(Some(place)) = provider.find_postal_code(ctx, &normalized_search).await {
+            return Ok(Location {
+                name: place.display_name(),
+                lat: place.lat,
+                lon: place.lon,
+            });
+        }
+    }
+
+    let mut results = provider
+        .search(ctx, &normalized_search, CHOICE_EMOJIS.len())
+        .await?;
+
+    // The same place can be returned multiple times, i.e. for different districts
+    let mut seen = HashSet::new();
+    results.retain(|result| seen.insert(result.display_name()));
+
+    let result = match results.len() {
+        0 => {
+            return Err(CommandError::from(format!(
+                "Couldn't find a location named `{}`",
+                search_arg
+            )))
+        }
+        1 => results.remove(0),
+        _ => select_location(ctx, msg, search_arg, results).await?,
+    };
+
+    Ok(Location {
+        name: result.display_name(),
+        lat: result.lat,
+        lon: result.lon,
+    })
+}
+
+/// Lets the user pick one of multiple matching locations by reacting with its number
+async fn select_location(
+    ctx: &Context,
+    msg: &Message,
+    search_arg: &str,
+    mut results: Vec<Place>,
+) -> Result<Place, CommandError> {
+    let choices = results
+        .iter()
+        .zip(CHOICE_EMOJIS.iter())
+        .map(|(result, emoji)| format!("{} {}", emoji, result.display_name()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let prompt = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(MAIN_COLOR)
+                    .title(format!("Multiple locations match {}", search_arg))
+                    .description(choices)
+                    .footer(|f| f.text("React with the number of your location"))
+            })
+        })
+        .await?;
+
+    // The choices are added in the background, so that early reactions aren't missed
+    let http = ctx.http.clone();
+    let (channel_id, prompt_id) = (prompt.channel_id, prompt.id);
+    let choice_count = results.len();
+    tokio::spawn(async move {
+        for emoji in CHOICE_EMOJIS.iter().take(choice_count) {
+            let reaction = ReactionType::Unicode(emoji.to_string());
+            if channel_id
+                .create_reaction(&http, prompt_id, reaction)
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let selection = prompt
+        .await_reaction(&ctx)
+        .author_id(msg.author.id)
+        .timeout(Duration::from_secs(CHOICE_TIMEOUT_SECS))
+        .filter(move |reaction| get_choice_index(&reaction.emoji, choice_count).is_some())
+        .await;
+
+    let _ = prompt.delete(&ctx.http).await;
+
+    selection
+        .and_then(|action| get_choice_index(&action.as_inner_ref().emoji, choice_count))
+        .map(|index| results.remove(index))
+        .ok_or_else(|| CommandError::from("No location was selected"))
+}
+
+fn get_choice_index(emoji: &ReactionType, choice_count: usize) -> Option<usize> {
+    CHOICE_EMOJIS
+        .iter()
+        .take(choice_count)
+        .position(|choice| reaction_matches(&ReactionType::Unicode(choice.to_string()), emoji))
+}
+
+/// Parses coordinates like "52.52, 13.40" or "52.52 13.40"
+fn parse_coordinates(search_arg: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = search_arg
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    match parts[..] {
+        [lat, lon] => {
+            let lat = lat.parse::<f64>().ok().filter(|lat| lat.abs() <= 90.0)?;
+            let lon = lon.parse::<f64>().ok().filter(|lon| lon.abs() <= 180.0)?;
+            Some((lat, lon))
+        }
+        _ => None,
+    }
+}
+
+async fn fetch_weather(ctx: &Context, location: &Location) -> Result<WeatherReport, CommandError> {
+    get_provider()
+        .fetch_weather(ctx, location.lat, location.lon)
+        .await
+}
+
+/// Describes the current rain and snow volume, or when precipitation starts within the next hour
+fn format_precipitation(weather: &WeatherReport, units: Units) -> String {
+    let mut lines = vec![];
+
+    if let Some(rain) = &weather.current.rain {
+        lines.push(format!(
+            "**Rain**: {}/h",
+            units.format_precipitation(rain.one_hour)
+        ));
+    }
+    if let Some(snow) = &weather.current.snow {
+        lines.push(format!(
+            "**Snow**: {}/h",
+            units.format_precipitation(snow.one_hour)
+        ));
+    }
+
+    if lines.is_empty() {
+        let start = weather
+            .minutely
+            .iter()
+            .find(|minute| minute.precipitation > 0.0)
+            .map(|minute| (minute.dt - weather.current.dt) / 60);
+
+        if let Some(minutes) = start {
+            lines.push(format!("Starting in about {} minutes", minutes.max(1)));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// Returns the emoji and description of the first weather condition. Apis always return one,
+/// but a missing condition is shown as unknown instead of failing.
+fn describe_condition(weather: &[Weather]) -> (String, String) {
+    match weather.first() {
+        Some(weather) => (
+            get_weather_emoji(&weather.icon),
+            uppercase_first(&weather.description),
+        ),
+        None => ("❔".to_string(), "Unknown".to_string()),
+    }
+}
+
+fn get_weather_image_url(code: &String) -> String {
+    format!("http://openweathermap.org/img/wn/{}@2x.png", code)
+}
+
+fn get_weather_emoji(code: &String) -> String {
+    match code.as_str() {
+        "01d" => "☀️",
+        "01n" => "🌕",
+        "02d" | "02n" => "⛅",
+        "03d" | "03n" => "☁️",
+        "04d" | "04n" => "☁️",
+        "09d" | "09n" => "🌧️",
+        "10d" | "10n" => "🌧️",
+        "11d" | "11n" => "🌩️",
+        "13d" | "13n" => "❄️",
+        "50d" | "50n" => "🌫️",
+        _ => "☀️",
+    }
+    .to_string()
+}
+
+// "%H:%M, %e %b %Y"
+fn format_timestamp(timestamp: i64, offset: i32, format: &str) -> String {
+    let date_time = DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc);
+    let local_time = date_time.with_timezone(&FixedOffset::east(offset));
+
+    local_time.format(format).to_string()
+}
+
+fn format_direction(degrees: i32) -> String {
+    match degrees {
+        x if x <= 45 || x > 315 => "North",
+        x if x <= 135 => "East",
+        x if x <= 225 => "South",
+        x if x <= 315 => "West",
+        _ => "",
+    }
+    .to_string()
+}
+
+/// A place found by a location search
+#[derive(Deserialize, Debug)]
+struct Place {
+    name: String,
+    lat: f64,
+    lon: f64,
+    country: String,
+    state: Option<String>,
+}
+
+impl Place {
+    fn display_name(&self) -> String {
+        match &self.state {
+            Some(state) => format!("{}, {}, {}", self.name, state, self.country),
+            None => format!("{}, {}", self.name, self.country),
+        }
+    }
+}
+
+/// The weather at a location. It follows the format of the OpenWeatherMap One Call api,
+/// which other providers are mapped to. Temperatures are in °C, speeds in m/s,
+/// distances in meters and precipitation in millimeters.
+#[derive(Deserialize, Debug)]
+struct WeatherReport {
+    timezone_offset: i32,
+    current: CurrentWeather,
+    // Minutely forecasts and alerts aren't available for every location
+    #[serde(default)]
+    minutely: Vec<MinutelyWeather>,
+    hourly: Vec<HourlyWeather>,
+    daily: Vec<DailyWeather>,
+    #[serde(default)]
+    alerts: Vec<Alert>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CurrentWeather {
+    dt: i64,
+    sunrise: i64,
+    sunset: i64,
+    temp: f64,
+    feels_like: f64,
+    pressure: i32,
+    humidity: i32,
+    clouds: i32,
+    uvi: f64,
+    visibility: Option<i32>,
+    wind_speed: f64,
+    wind_deg: i32,
+    rain: Option<Precipitation>,
+    snow: Option<Precipitation>,
+    weather: Vec<Weather>,
+}
+#[derive(Deserialize, Debug)]
+struct MinutelyWeather {
+    dt: i64,
+    precipitation: f64,
+}
+#[derive(Deserialize, Debug)]
+struct HourlyWeather {
+    dt: i64,
+    temp: f64,
+    wind_speed: f64,
+    pop: f64,
+    rain: Option<Precipitation>,
+    snow: Option<Precipitation>,
+    weather: Vec<Weather>,
+}
+#[derive(Deserialize, Debug)]
+struct DailyWeather {
+    dt: i64,
+    sunrise: i64,
+    sunset: i64,
+    temp: Temp,
+    humidity: i32,
+    wind_speed: f64,
+    uvi: f64,
+    pop: f64,
+    // Daily rain and snow volumes are plain numbers in millimeters
+    rain: Option<f64>,
+    snow: Option<f64>,
+    weather: Vec<Weather>,
+}
+#[derive(Deserialize, Debug)]
+struct Precipitation {
+    #[serde(rename = "1h")]
+    one_hour: f64,
+}
+#[derive(Deserialize, Debug)]
+struct Alert {
+    sender_name: String,
+    event: String,
+    start: i64,
+    end: i64,
+    description: String,
+}
+/// A weather condition with its OpenWeatherMap icon code, i.e. "01d"
+#[derive(Deserialize, Debug)]
+struct Weather {
+    description: String,
+    icon: String,
+}
+#[derive(Deserialize, Debug)]
+struct Temp {
+    min: f64,
+    max: f64,
+}
@@ -0,0 +1,301 @@
+use serde::Deserialize;
+use serenity::{async_trait, framework::standard::CommandError, prelude::Context};
+
+use super::{
+    provider::{get_cached, WeatherProvider},
+    CurrentWeather, DailyWeather, HourlyWeather, Place, Precipitation, Temp, Weather,
+    WeatherReport,
+};
+
+const HOURLY_VARIABLES: &str = "temperature_2m,apparent_temperature,relativehumidity_2m,\
+    surface_pressure,cloudcover,visibility,windspeed_10m,precipitation_probability,rain,snowfall,\
+    weathercode,uv_index,is_day";
+const DAILY_VARIABLES: &str = "weathercode,temperature_2m_max,temperature_2m_min,sunrise,sunset,\
+    precipitation_probability_max,rain_sum,snowfall_sum,uv_index_max,windspeed_10m_max";
+const FORECAST_DAYS: usize = 8;
+// Open-Meteo measures snowfall in centimeters
+const CM_TO_MM: f64 = 10.0;
+
+/// The keyless Open-Meteo api, which is used when no OpenWeatherMap api key is set
+pub struct OpenMeteo;
+
+#[async_trait]
+impl WeatherProvider for OpenMeteo {
+    async fn search(
+        &self,
+        ctx: &Context,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, CommandError> {
+        let response: SearchResponse = get_cached(
+            ctx,
+            "https://geocoding-api.open-meteo.com/v1/search",
+            vec![
+                ("name", query.to_string()),
+                ("count", limit.to_string()),
+                ("language", "en".to_string()),
+            ],
+        )
+        .await?;
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| Place {
+                name: result.name,
+                lat: result.latitude,
+                lon: result.longitude,
+                country: result.country_code.unwrap_or_default(),
+                state: result.admin1,
+            })
+            .collect())
+    }
+
+    async fn fetch_weather(
+        &self,
+        ctx: &Context,
+        lat: f64,
+        lon: f64,
+    ) -> Result<WeatherReport, CommandError> {
+        // Coordinates are rounded to about a kilometer, so nearby lookups share their cache entries
+        let response: ForecastResponse = get_cached(
+            ctx,
+            "https://api.open-meteo.com/v1/forecast",
+            vec![
+                ("latitude", format!("{:.2}", lat)),
+                ("longitude", format!("{:.2}", lon)),
+                ("current_weather", "true".to_string()),
+                ("hourly", HOURLY_VARIABLES.to_string()),
+                ("daily", DAILY_VARIABLES.to_string()),
+                ("forecast_days", FORECAST_DAYS.to_string()),
+                ("windspeed_unit", "ms".to_string()),
+                ("timeformat", "unixtime".to_string()),
+                ("timezone", "auto".to_string()),
+            ],
+        )
+        .await?;
+
+        if response.daily.time.is_empty() || response.hourly.time.is_empty() {
+            return Err(CommandError::from(
+                "There was an error parsing the weather api response",
+            ));
+        }
+
+        Ok(create_report(response))
+    }
+}
+
+/// Maps the Open-Meteo forecast to the One Call format the embeds are made from
+fn create_report(response: ForecastResponse) -> WeatherReport {
+    let hourly = &response.hourly;
+    let daily = &response.daily;
+    let current = &response.current_weather;
+
+    // Current values that Open-Meteo only has hourly forecasts for are taken from this hour
+    let now = hourly
+        .time
+        .iter()
+        .rposition(|time| *time <= current.time)
+        .unwrap_or_default();
+
+    let current = CurrentWeather {
+        dt: current.time,
+        sunrise: daily.sunrise.first().copied().unwrap_or_default(),
+        sunset: daily.sunset.first().copied().unwrap_or_default(),
+        temp: current.temperature,
+        feels_like: value_at(&hourly.apparent_temperature, now),
+        pressure: value_at(&hourly.surface_pressure, now).round() as i32,
+        humidity: value_at(&hourly.relativehumidity_2m, now).round() as i32,
+        clouds: value_at(&hourly.cloudcover, now).round() as i32,
+        uvi: value_at(&hourly.uv_index, now),
+        visibility: hourly
+            .visibility
+            .get(now)
+            .copied()
+            .flatten()
+            .map(|v| v as i32),
+        wind_speed: current.windspeed,
+        wind_deg: current.winddirection.round() as i32,
+        rain: precipitation_at(&hourly.rain, now, 1.0),
+        snow: precipitation_at(&hourly.snowfall, now, CM_TO_MM),
+        weather: vec![map_weather_code(current.weathercode, current.is_day == 1)],
+    };
+
+    let hourly_weather = (now..hourly.time.len())
+        .map(|hour| HourlyWeather {
+            dt: hourly.time[hour],
+            temp: value_at(&hourly.temperature_2m, hour),
+            wind_speed: value_at(&hourly.windspeed_10m, hour),
+            pop: value_at(&hourly.precipitation_probability, hour) / 100.0,
+            rain: precipitation_at(&hourly.rain, hour, 1.0),
+            snow: precipitation_at(&hourly.snowfall, hour, CM_TO_MM),
+            weather: vec![map_weather_code(
+                hourly
+                    .weathercode
+                    .get(hour)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default(),
+                hourly.is_day.get(hour).copied().flatten() == Some(1),
+            )],
+        })
+        .collect();
+
+    // Hours start at local midnight of the first day, as the timezone of the location is used
+    let daily_weather = (0..daily.time.len())
+        .map(|day| {
+            let humidity_values: Vec<f64> = (day * 24..(day + 1) * 24)
+                .filter_map(|hour| hourly.relativehumidity_2m.get(hour).copied().flatten())
+                .collect();
+
+            DailyWeather {
+                dt: daily.time[day],
+                sunrise: daily.sunrise.get(day).copied().unwrap_or_default(),
+                sunset: daily.sunset.get(day).copied().unwrap_or_default(),
+                temp: Temp {
+                    min: value_at(&daily.temperature_2m_min, day),
+                    max: value_at(&daily.temperature_2m_max, day),
+                },
+                humidity: match humidity_values.is_empty() {
+                    true => 0,
+                    false => (humidity_values.iter().sum::<f64>() / humidity_values.len() as f64)
+                        .round() as i32,
+                },
+                wind_speed: value_at(&daily.windspeed_10m_max, day),
+                uvi: value_at(&daily.uv_index_max, day),
+                pop: value_at(&daily.precipitation_probability_max, day) / 100.0,
+                rain: Some(value_at(&daily.rain_sum, day)).filter(|v| *v > 0.0),
+                snow: Some(value_at(&daily.snowfall_sum, day) * CM_TO_MM).filter(|v| *v > 0.0),
+                weather: vec![map_weather_code(
+                    daily
+                        .weathercode
+                        .get(day)
+                        .copied()
+                        .flatten()
+                        .unwrap_or_default(),
+                    true,
+                )],
+            }
+        })
+        .collect();
+
+    WeatherReport {
+        timezone_offset: response.utc_offset_seconds,
+        current,
+        minutely: vec![],
+        hourly: hourly_weather,
+        daily: daily_weather,
+        alerts: vec![],
+    }
+}
+
+// Values can be missing at the end of the forecast range
+fn value_at(values: &[Option<f64>], index: usize) -> f64 {
+    values.get(index).copied().flatten().unwrap_or_default()
+}
+
+fn precipitation_at(values: &[Option<f64>], index: usize, factor: f64) -> Option<Precipitation> {
+    Some(value_at(values, index) * factor)
+        .filter(|v| *v > 0.0)
+        .map(|one_hour| Precipitation { one_hour })
+}
+
+/// Maps WMO weather codes to a description and the OpenWeatherMap icon of the same condition
+fn map_weather_code(code: u8, is_day: bool) -> Weather {
+    let (description, icon) = match code {
+        0 => ("clear sky", "01"),
+        1 => ("mainly clear", "02"),
+        2 => ("partly cloudy", "03"),
+        3 => ("overcast", "04"),
+        45 | 48 => ("fog", "50"),
+        51 | 53 | 55 => ("drizzle", "09"),
+        56 | 57 => ("freezing drizzle", "09"),
+        61 => ("light rain", "10"),
+        63 => ("moderate rain", "10"),
+        65 => ("heavy rain", "10"),
+        66 | 67 => ("freezing rain", "13"),
+        71 => ("light snow", "13"),
+        73 => ("moderate snow", "13"),
+        75 => ("heavy snow", "13"),
+        77 => ("snow grains", "13"),
+        80..=82 => ("rain showers", "09"),
+        85 | 86 => ("snow showers", "13"),
+        95..=99 => ("thunderstorm", "11"),
+        _ => ("unknown", "01"),
+    };
+
+    Weather {
+        description: description.to_string(),
+        icon: format!(
+            "{}{}",
+            icon,
+            match is_day {
+                true => "d",
+                false => "n",
+            }
+        ),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResponse {
+    // Missing if nothing was found
+    #[serde(default)]
+    results: Vec<SearchResult>,
+}
+#[derive(Deserialize, Debug)]
+struct SearchResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+    admin1: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ForecastResponse {
+    utc_offset_seconds: i32,
+    current_weather: CurrentResponse,
+    hourly: HourlyResponse,
+    daily: DailyResponse,
+}
+#[derive(Deserialize, Debug)]
+struct CurrentResponse {
+    time: i64,
+    temperature: f64,
+    windspeed: f64,
+    winddirection: f64,
+    weathercode: u8,
+    is_day: u8,
+}
+#[derive(Deserialize, Debug)]
+struct HourlyResponse {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    apparent_temperature: Vec<Option<f64>>,
+    relativehumidity_2m: Vec<Option<f64>>,
+    surface_pressure: Vec<Option<f64>>,
+    cloudcover: Vec<Option<f64>>,
+    visibility: Vec<Option<f64>>,
+    windspeed_10m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    rain: Vec<Option<f64>>,
+    snowfall: Vec<Option<f64>>,
+    weathercode: Vec<Option<u8>>,
+    uv_index: Vec<Option<f64>>,
+    is_day: Vec<Option<u8>>,
+}
+#[derive(Deserialize, Debug)]
+struct DailyResponse {
+    time: Vec<i64>,
+    weathercode: Vec<Option<u8>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    sunrise: Vec<i64>,
+    sunset: Vec<i64>,
+    precipitation_probability_max: Vec<Option<f64>>,
+    rain_sum: Vec<Option<f64>>,
+    snowfall_sum: Vec<Option<f64>>,
+    uv_index_max: Vec<Option<f64>>,
+    windspeed_10m_max: Vec<Option<f64>>,
+}
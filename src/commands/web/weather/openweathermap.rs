@@ -0,0 +1,89 @@
+use serde::Deserialize;
+use serenity::{async_trait, framework::standard::CommandError, prelude::Context};
+use std::env;
+
+use super::{
+    provider::{get_cached, WeatherProvider},
+    Place, WeatherReport,
+};
+
+pub const OPEN_WEATHER_MAP_TOKEN_VAR: &str = "OPEN_WEATHER_MAP_TOKEN";
+
+pub struct OpenWeatherMap;
+
+#[async_trait]
+impl WeatherProvider for OpenWeatherMap {
+    async fn search(
+        &self,
+        ctx: &Context,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, CommandError> {
+        get_cached(
+            ctx,
+            "http://api.openweathermap.org/geo/1.0/direct",
+            vec![
+                ("appid", get_token()?),
+                ("q", query.to_string()),
+                ("limit", limit.to_string()),
+            ],
+        )
+        .await
+    }
+
+    async fn find_postal_code(
+        &self,
+        ctx: &Context,
+        postal_code: &str,
+    ) -> Result<Option<Place>, CommandError> {
+        let result: ZipGeocodingResponse = get_cached(
+            ctx,
+            "http://api.openweathermap.org/geo/1.0/zip",
+            vec![("appid", get_token()?), ("zip", postal_code.to_string())],
+        )
+        .await?;
+
+        Ok(Some(Place {
+            name: format!("{} {}", result.zip, result.name),
+            lat: result.lat,
+            lon: result.lon,
+            country: result.country,
+            state: None,
+        }))
+    }
+
+    async fn fetch_weather(
+        &self,
+        ctx: &Context,
+        lat: f64,
+        lon: f64,
+    ) -> Result<WeatherReport, CommandError> {
+        // Coordinates are rounded to about a kilometer, so nearby lookups share their cache entries
+        get_cached(
+            ctx,
+            "http://api.openweathermap.org/data/2.5/onecall",
+            vec![
+                ("appid", get_token()?),
+                ("lat", format!("{:.2}", lat)),
+                ("lon", format!("{:.2}", lon)),
+                ("units", "metric".to_string()),
+            ],
+        )
+        .await
+    }
+}
+
+fn get_token() -> Result<String, CommandError> {
+    env::var(OPEN_WEATHER_MAP_TOKEN_VAR).map_err(|_| {
+        CommandError::from("The bot owner didn't provide the OpenWeatherMap api key".to_string())
+    })
+}
+
+#[derive(Deserialize, Debug)]
+struct ZipGeocodingResponse {
+    zip: String,
+    name: String,
+    lat: f64,
+    lon: f64,
+    country: String,
+}
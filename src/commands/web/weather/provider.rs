@@ -0,0 +1,116 @@
+use log::error;
+use serde::de::DeserializeOwned;
+use serenity::{async_trait, framework::standard::CommandError, prelude::Context};
+use std::{env, error::Error};
+
+use super::{
+    openmeteo::OpenMeteo,
+    openweathermap::{OpenWeatherMap, OPEN_WEATHER_MAP_TOKEN_VAR},
+    Place, WeatherReport,
+};
+use crate::core::context::{HttpClientContainer, WeatherCacheContainer};
+
+/// A source of location searches and weather data
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    /// Searches places by name, returning at most `limit` results
+    async fn search(
+        &self,
+        ctx: &Context,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, CommandError>;
+
+    /// Looks up a postal code. Providers without postal code support don't find anything.
+    async fn find_postal_code(
+        &self,
+        _ctx: &Context,
+        _postal_code: &str,
+    ) -> Result<Option<Place>, CommandError> {
+        Ok(None)
+    }
+
+    async fn fetch_weather(
+        &self,
+        ctx: &Context,
+        lat: f64,
+        lon: f64,
+    ) -> Result<WeatherReport, CommandError>;
+}
+
+/// Returns the provider set with the WEATHER_PROVIDER environment variable.
+/// Without one, OpenWeatherMap is used if its api key is set, otherwise the keyless Open-Meteo.
+pub fn get_provider() -> Box<dyn WeatherProvider> {
+    match env::var("WEATHER_PROVIDER").as_deref() {
+        Ok("openweathermap") => Box::new(OpenWeatherMap),
+        Ok("openmeteo") => Box::new(OpenMeteo),
+        _ if env::var(OPEN_WEATHER_MAP_TOKEN_VAR).is_ok() => Box::new(OpenWeatherMap),
+        _ => Box::new(OpenMeteo),
+    }
+}
+
+/// Sends a request to a weather api, unless the same request has been answered recently
+pub async fn get_cached<T: DeserializeOwned>(
+    ctx: &Context,
+    url: &str,
+    query: Vec<(&str, String)>,
+) -> Result<T, CommandError> {
+    let (client, cache) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<HttpClientContainer>().unwrap().clone(),
+            data.get::<WeatherCacheContainer>().unwrap().clone(),
+        )
+    };
+
+    // The api key isn't part of the key, so it doesn't end up anywhere besides the request
+    let cache_key = format!(
+        "{}?{:?}",
+        url,
+        query
+            .iter()
+            .filter(|(name, _)| *name != "appid")
+            .collect::<Vec<_>>()
+    );
+    let cached_body = cache.lock().await.get(&cache_key);
+    let is_cached = cached_body.is_some();
+
+    let body = match cached_body {
+        Some(body) => body,
+        None => fetch_body(&client, url, &query).await.map_err(|why| {
+            // Errors of reqwest contain the full url, including the api key in the query string
+            error!(
+                "Weather api request to {} failed with status {:?}: {:?}",
+                url,
+                why.status(),
+                why.source()
+            );
+            CommandError::from("The weather api request failed")
+        })?,
+    };
+
+    let response = serde_json::from_str(&body)
+        .map_err(|_| CommandError::from("There was an error parsing the weather api response"))?;
+
+    // Only responses that could be parsed are cached, so failed requests are retried
+    if !is_cached {
+        cache.lock().await.insert(cache_key, body);
+    }
+
+    Ok(response)
+}
+
+async fn fetch_body(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<String, reqwest::Error> {
+    client
+        .get(url)
+        .query(query)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// An in-memory cache whose entries expire a fixed time after they've been inserted.
+/// Lookups are counted, so the hit rate can be shown.
+pub struct TtlCache<V> {
+    ttl: Duration,
+    entries: HashMap<String, (Instant, V)>,
+    hits: u64,
+    misses: u64,
+}
+
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the value for the key if there is one that hasn't expired yet
+    pub fn get(&mut self, key: &str) -> Option<V> {
+        let ttl = self.ttl;
+        let value = self
+            .entries
+            .get(key)
+            .filter(|(inserted, _)| inserted.elapsed() < ttl)
+            .map(|(_, value)| value.clone());
+
+        match value {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        value
+    }
+
+    pub fn insert(&mut self, key: String, value: V) {
+        // Expired entries are only cleaned up on inserts, which keeps lookups cheap
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (inserted, _)| inserted.elapsed() < ttl);
+        self.entries.insert(key, (Instant::now(), value));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
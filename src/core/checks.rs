@@ -1,7 +1,7 @@
 use serenity::{
     client::Context,
     framework::standard::{macros::check, Args, CommandOptions, Reason},
-    model::channel::Message,
+    model::{channel::Message, guild::Member},
 };
 
 #[check]
@@ -47,24 +47,30 @@ async fn admin_check(
 async fn mod_check(
     ctx: &Context,
     msg: &Message,
-    args: &mut Args,
-    options: &CommandOptions,
+    _: &mut Args,
+    _: &CommandOptions,
 ) -> Result<(), Reason> {
     let member = msg.member(&ctx).await.expect("can't get member");
-    let roles = member.roles(&ctx).await.expect("can't get roles");
-
-    if admin_check(ctx, msg, args, options).await.is_ok() {
-        return Ok(());
-    }
 
-    // todo: allow admins to set a mod role, store it in a database, and check against that
-    match roles
-        .iter()
-        .any(|role| role.id.to_string() == "134040353517862912")
-    {
+    match is_moderator(ctx, &member).await {
         true => Ok(()),
         false => Err(Reason::User(
             "This command can only be run as a moderator".to_string(),
         )),
     }
 }
+
+/// Returns whether the member is an administrator or has the moderator role
+pub async fn is_moderator(ctx: &Context, member: &Member) -> bool {
+    if let Ok(perms) = member.permissions(&ctx).await {
+        if perms.administrator() {
+            return true;
+        }
+    }
+
+    // todo: allow admins to set a mod role, store it in a database, and check against that
+    member
+        .roles
+        .iter()
+        .any(|role_id| role_id.to_string() == "134040353517862912")
+}
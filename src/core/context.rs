@@ -3,12 +3,18 @@ use serenity::{
     model::prelude::{CurrentApplicationInfo, CurrentUser},
     prelude::*,
 };
-use std::sync::Arc;
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 
 extern crate chrono;
 use chrono::{DateTime, Utc};
 use sysinfo::System;
 
+use super::{
+    cache::TtlCache,
+    scheduler::{ActionExecutor, ActionKind},
+    storage::Store,
+};
+
 pub struct ShardManagerContainer;
 impl TypeMapKey for ShardManagerContainer {
     type Value = Arc<Mutex<ShardManager>>;
@@ -33,3 +39,24 @@ pub struct BotUserContainer;
 impl TypeMapKey for BotUserContainer {
     type Value = CurrentUser;
 }
+
+pub struct HttpClientContainer;
+impl TypeMapKey for HttpClientContainer {
+    type Value = reqwest::Client;
+}
+
+/// Raw weather api responses by request
+pub struct WeatherCacheContainer;
+impl TypeMapKey for WeatherCacheContainer {
+    type Value = Arc<Mutex<TtlCache<String>>>;
+}
+
+pub struct StoreContainer<T>(PhantomData<T>);
+impl<T: Send + Sync + 'static> TypeMapKey for StoreContainer<T> {
+    type Value = Arc<RwLock<Store<T>>>;
+}
+
+pub struct ActionExecutorContainer;
+impl TypeMapKey for ActionExecutorContainer {
+    type Value = HashMap<ActionKind, Arc<dyn ActionExecutor>>;
+}
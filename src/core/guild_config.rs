@@ -0,0 +1,60 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serenity::{
+    framework::standard::{Args, CommandError, CommandResult},
+    model::channel::Message,
+    prelude::Context,
+};
+use std::collections::HashMap;
+
+use super::{constants::MAIN_COLOR, storage::get_store};
+
+/// A store that keeps a config per guild
+pub trait GuildConfigs {
+    type Config: Default;
+
+    /// Configs by guild id
+    fn configs_mut(&mut self) -> &mut HashMap<u64, Self::Config>;
+}
+
+/// Updates the config of the guild a command was used in, creating a default one if there's none.
+/// The update returns the confirmation that is sent to the channel.
+pub async fn update_guild_config<T, F>(ctx: &Context, msg: &Message, update: F) -> CommandResult
+where
+    T: GuildConfigs + Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+    F: FnOnce(&mut T::Config) -> Result<String, CommandError>,
+{
+    let guild_id = msg.guild_id.unwrap();
+
+    let description = {
+        let store = get_store::<T>(ctx).await;
+        let mut store = store.write().await;
+
+        let description = update(store.configs_mut().entry(guild_id.0).or_default())?;
+        store.save();
+        description
+    };
+
+    let _ = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.colour(MAIN_COLOR).description(description))
+        })
+        .await;
+
+    Ok(())
+}
+
+pub fn parse_on_off(args: &mut Args) -> Result<bool, CommandError> {
+    match args.single::<String>()?.to_lowercase().as_str() {
+        "on" | "true" | "enable" => Ok(true),
+        "off" | "false" | "disable" => Ok(false),
+        _ => Err(CommandError::from("Please supply either `on` or `off`")),
+    }
+}
+
+pub fn on_off_str(value: bool) -> &'static str {
+    match value {
+        true => "on",
+        false => "off",
+    }
+}
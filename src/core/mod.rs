@@ -1,5 +1,10 @@
+pub mod cache;
 pub mod checks;
 pub mod constants;
 pub mod context;
+pub mod guild_config;
+pub mod moderation;
+pub mod scheduler;
+pub mod storage;
 pub mod util;
 //pub mod pagination;
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use serenity::{
+    client::Context,
+    framework::standard::CommandError,
+    model::{
+        guild::Role,
+        id::{GuildId, RoleId, UserId},
+        Permissions,
+    },
+};
+use std::collections::HashMap;
+
+use super::constants::ERROR_COLOR;
+
+pub const DEFAULT_REASON: &str = "No reason given";
+
+/// Makes sure that both the moderator and the bot itself are placed above the target
+/// in the guild's role hierarchy, so that moderators can't punish their peers or superiors.
+pub async fn check_hierarchy(
+    ctx: &Context,
+    guild_id: GuildId,
+    moderator_id: UserId,
+    target_id: UserId,
+) -> Result<(), CommandError> {
+    let bot_id = ctx.cache.current_user_id().await;
+
+    if target_id == moderator_id {
+        return Err(CommandError::from("You can't use this on yourself"));
+    }
+
+    if target_id == bot_id {
+        return Err(CommandError::from(
+            "Nice try, but I won't do that to myself",
+        ));
+    }
+
+    let (owner_id, roles) = get_guild_roles(ctx, guild_id).await?;
+
+    // Users that left the guild already can't be compared and are always fair game
+    let target_position =
+        match get_member_position(ctx, guild_id, target_id, owner_id, &roles).await {
+            Some(position) => position,
+            None => return Ok(()),
+        };
+
+    let moderator_position = get_member_position(ctx, guild_id, moderator_id, owner_id, &roles)
+        .await
+        .unwrap_or_default();
+    let bot_position = get_member_position(ctx, guild_id, bot_id, owner_id, &roles)
+        .await
+        .unwrap_or_default();
+
+    if moderator_position <= target_position {
+        return Err(CommandError::from(
+            "You can't use this on members with an equal or higher role than yours",
+        ));
+    }
+
+    if bot_position <= target_position {
+        return Err(CommandError::from(
+            "I can't use this on members with an equal or higher role than mine",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Makes sure that a role is placed below the highest role of both the moderator and the bot,
+/// so that moderators can't hand out roles more powerful than their own.
+/// Returns the role, i.e. for further checks of its permissions.
+pub async fn check_role_position(
+    ctx: &Context,
+    guild_id: GuildId,
+    moderator_id: UserId,
+    role_id: RoleId,
+) -> Result<Role, CommandError> {
+    let bot_id = ctx.cache.current_user_id().await;
+    let (owner_id, roles) = get_guild_roles(ctx, guild_id).await?;
+
+    let role = match roles.get(&role_id) {
+        Some(role) if role.id.0 != guild_id.0 && !role.managed => role.clone(),
+        _ => return Err(CommandError::from("That role can't be given to members")),
+    };
+
+    let moderator_position = get_member_position(ctx, guild_id, moderator_id, owner_id, &roles)
+        .await
+        .unwrap_or_default();
+    let bot_position = get_member_position(ctx, guild_id, bot_id, owner_id, &roles)
+        .await
+        .unwrap_or_default();
+
+    if moderator_position <= role.position {
+        return Err(CommandError::from(
+            "You can't use roles that are equal to or higher than your highest role",
+        ));
+    }
+
+    if bot_position <= role.position {
+        return Err(CommandError::from(
+            "I can't use roles that are equal to or higher than my highest role",
+        ));
+    }
+
+    Ok(role)
+}
+
+/// Makes sure that a role handed out to members automatically doesn't grant any moderation powers
+pub fn check_unprivileged_role(role: &Role) -> Result<(), CommandError> {
+    let privileged = Permissions::ADMINISTRATOR
+        | Permissions::MANAGE_GUILD
+        | Permissions::MANAGE_ROLES
+        | Permissions::MANAGE_CHANNELS
+        | Permissions::MANAGE_MESSAGES
+        | Permissions::MANAGE_NICKNAMES
+        | Permissions::MANAGE_WEBHOOKS
+        | Permissions::MANAGE_EMOJIS
+        | Permissions::KICK_MEMBERS
+        | Permissions::BAN_MEMBERS;
+
+    match role.permissions.intersects(privileged) {
+        true => Err(CommandError::from(
+            "Roles with administrator or moderation permissions can't be handed out automatically",
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Returns the guild owner and the roles of a guild, from the cache if possible
+async fn get_guild_roles(
+    ctx: &Context,
+    guild_id: GuildId,
+) -> Result<(UserId, HashMap<RoleId, Role>), CommandError> {
+    match ctx
+        .cache
+        .guild_field(guild_id, |g| (g.owner_id, g.roles.clone()))
+        .await
+    {
+        Some(guild_info) => Ok(guild_info),
+        None => {
+            let guild = guild_id.to_partial_guild(&ctx.http).await?;
+            Ok((guild.owner_id, guild.roles))
+        }
+    }
+}
+
+/// Returns the position of a member's highest role, with the guild owner ranking above everyone.
+/// Returns None if the user isn't a member of the guild.
+async fn get_member_position(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    owner_id: UserId,
+    roles: &HashMap<RoleId, Role>,
+) -> Option<i64> {
+    if user_id == owner_id {
+        return Some(i64::MAX);
+    }
+
+    let member = guild_id.member(ctx, user_id).await.ok()?;
+
+    Some(
+        member
+            .roles
+            .iter()
+            .filter_map(|id| roles.get(id))
+            .map(|role| role.position)
+            .max()
+            .unwrap_or_default(),
+    )
+}
+
+/// Informs a user via DM about an action taken against them.
+/// Users might have their DMs closed, so failures are silently ignored.
+pub async fn notify_user(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    action: &str,
+    reason: &str,
+) {
+    let guild_name = match guild_id.name(&ctx.cache).await {
+        Some(name) => name,
+        None => return,
+    };
+
+    if let Ok(user) = user_id.to_user(ctx).await {
+        let _ = user
+            .direct_message(ctx, |m| {
+                m.embed(|e| {
+                    e.colour(ERROR_COLOR)
+                        .title(format!("You have been {} in {}", action, guild_name))
+                        .field("Reason", reason, false)
+                })
+            })
+            .await;
+    }
+}
+
+/// Sets or lifts (when `until` is None) a member's timeout
+pub async fn timeout_member(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    until: Option<DateTime<Utc>>,
+) -> Result<(), CommandError> {
+    let mut map: Map<String, Value> = Map::new();
+    map.insert(
+        "communication_disabled_until".to_string(),
+        json!(until.map(|date| date.to_rfc3339())),
+    );
+
+    ctx.http.edit_member(guild_id.0, user_id.0, &map).await?;
+
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    client::Context,
+    http::HttpError,
+    prelude::{SerenityError, TypeMap},
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use super::{context::ActionExecutorContainer, storage::get_store};
+
+const SCHEDULER_INTERVAL_SECS: u64 = 5;
+// Failed tasks are retried after 1, 2, 4 and 8 minutes before they're given up
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_BASE_SECS: i64 = 60;
+
+static SCHEDULER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ScheduleData {
+    next_id: u64,
+    tasks: Vec<ScheduledTask>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduledTask {
+    pub id: u64,
+    pub due: DateTime<Utc>,
+    pub action: ScheduledAction,
+    // Failed runs so far
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+/// An action that is run by the scheduler once it's due
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduledAction {
+    Unban {
+        guild_id: u64,
+        user_id: u64,
+    },
+    RemoveRole {
+        guild_id: u64,
+        user_id: u64,
+        role_id: u64,
+    },
+    AddRoles {
+        guild_id: u64,
+        user_id: u64,
+        role_ids: Vec<u64>,
+    },
+    /// Reminders are sent to the channel they were created in, or via DM if there's no channel
+    Reminder {
+        user_id: u64,
+        channel_id: Option<u64>,
+        text: String,
+        created: DateTime<Utc>,
+    },
+    /// Daily forecasts schedule their next post when they're delivered
+    WeatherForecast {
+        channel_id: u64,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ActionKind {
+    Unban,
+    RemoveRole,
+    AddRoles,
+    Reminder,
+    WeatherForecast,
+}
+
+impl ScheduledAction {
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            ScheduledAction::Unban { .. } => ActionKind::Unban,
+            ScheduledAction::RemoveRole { .. } => ActionKind::RemoveRole,
+            ScheduledAction::AddRoles { .. } => ActionKind::AddRoles,
+            ScheduledAction::Reminder { .. } => ActionKind::Reminder,
+            ScheduledAction::WeatherForecast { .. } => ActionKind::WeatherForecast,
+        }
+    }
+}
+
+/// Runs due actions of one kind. Executors live next to the commands that schedule their actions
+/// and are registered with `register_executor` on startup.
+#[async_trait]
+pub trait ActionExecutor: Send + Sync {
+    /// Runs a due action. Failed actions are retried a few times, unless discord rejected them.
+    async fn run(&self, ctx: &Context, action: &ScheduledAction) -> Result<(), SerenityError>;
+}
+
+/// Makes an executor run all due actions of the given kind
+pub fn register_executor<E>(data: &mut TypeMap, kind: ActionKind, executor: E)
+where
+    E: ActionExecutor + 'static,
+{
+    data.entry::<ActionExecutorContainer>()
+        .or_default()
+        .insert(kind, Arc::new(executor));
+}
+
+/// Persists an action to be run at the given time. Returns the id of the scheduled task.
+pub async fn schedule(ctx: &Context, due: DateTime<Utc>, action: ScheduledAction) -> u64 {
+    let store = get_store::<ScheduleData>(ctx).await;
+    let mut store = store.write().await;
+
+    store.next_id += 1;
+    let id = store.next_id;
+    store.tasks.push(ScheduledTask {
+        id,
+        due,
+        action,
+        attempts: 0,
+    });
+
+    store.save();
+    id
+}
+
+/// Returns all pending tasks that match the filter, ordered by their due time
+pub async fn get_tasks<F>(ctx: &Context, filter: F) -> Vec<ScheduledTask>
+where
+    F: Fn(&ScheduledTask) -> bool,
+{
+    let store = get_store::<ScheduleData>(ctx).await;
+    let store = store.read().await;
+
+    let mut tasks: Vec<ScheduledTask> = store.tasks.iter().filter(|t| filter(t)).cloned().collect();
+    tasks.sort_by_key(|t| t.due);
+    tasks
+}
+
+/// Removes a pending task if it matches the filter. Returns whether a task has been removed.
+pub async fn cancel<F>(ctx: &Context, id: u64, filter: F) -> bool
+where
+    F: Fn(&ScheduledTask) -> bool,
+{
+    let store = get_store::<ScheduleData>(ctx).await;
+    let mut store = store.write().await;
+
+    let count_before = store.tasks.len();
+    store.tasks.retain(|t| t.id != id || !filter(t));
+
+    if store.tasks.len() == count_before {
+        return false;
+    }
+
+    store.save();
+    true
+}
+
+/// Removes all pending tasks that match the filter. Returns how many tasks have been removed.
+pub async fn cancel_all<F>(ctx: &Context, filter: F) -> usize
+where
+    F: Fn(&ScheduledTask) -> bool,
+{
+    let store = get_store::<ScheduleData>(ctx).await;
+    let mut store = store.write().await;
+
+    let count_before = store.tasks.len();
+    store.tasks.retain(|t| !filter(t));
+
+    let removed = count_before - store.tasks.len();
+    if removed > 0 {
+        store.save();
+    }
+    removed
+}
+
+/// Starts the scheduler loop. Tasks that became due while the bot was offline are run right away.
+/// Calling this multiple times (i.e. on reconnects) only starts a single loop.
+pub fn start(ctx: Context) {
+    if SCHEDULER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            run_due_tasks(&ctx).await;
+        }
+    });
+}
+
+async fn run_due_tasks(ctx: &Context) {
+    let now = Utc::now();
+
+    // Tasks stay in the store while they run, so they aren't lost if the bot stops meanwhile.
+    // The loop waits for each run to finish, so a task can't be picked up twice.
+    let due_tasks: Vec<ScheduledTask> = {
+        let store = get_store::<ScheduleData>(ctx).await;
+        let store = store.read().await;
+
+        store
+            .tasks
+            .iter()
+            .filter(|t| t.due <= now)
+            .cloned()
+            .collect()
+    };
+
+    if due_tasks.is_empty() {
+        return;
+    }
+
+    let executors = {
+        let data = ctx.data.read().await;
+        data.get::<ActionExecutorContainer>()
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    for task in due_tasks {
+        info!("Running scheduled task {} (due {})", task.id, task.due);
+
+        let kind = task.action.kind();
+        let result = match executors.get(&kind) {
+            Some(executor) => executor.run(ctx, &task.action).await,
+            None => Err(SerenityError::Other(
+                "No executor registered for the action",
+            )),
+        };
+
+        let store = get_store::<ScheduleData>(ctx).await;
+        let mut store = store.write().await;
+
+        match result {
+            Ok(()) => store.tasks.retain(|t| t.id != task.id),
+            Err(why) if task.attempts + 1 < MAX_ATTEMPTS && is_transient(&why) => {
+                let delay = chrono::Duration::seconds(RETRY_BASE_SECS << task.attempts);
+                warn!(
+                    "Scheduled task {} ({:?}) failed, retrying in {}s: {:?}",
+                    task.id,
+                    kind,
+                    delay.num_seconds(),
+                    why
+                );
+
+                // The task might have been cancelled while it ran
+                if let Some(stored_task) = store.tasks.iter_mut().find(|t| t.id == task.id) {
+                    stored_task.attempts += 1;
+                    stored_task.due = Utc::now() + delay;
+                }
+            }
+            Err(why) => {
+                error!(
+                    "Scheduled task {} ({:?}) failed for good after {} attempts: {:?}",
+                    task.id,
+                    kind,
+                    task.attempts + 1,
+                    why
+                );
+                store.tasks.retain(|t| t.id != task.id);
+            }
+        }
+
+        store.save();
+    }
+}
+
+/// Client errors other than rate limits mean discord won't ever accept the request,
+/// i.e. because the ban or member doesn't exist anymore. Everything else is worth a retry.
+fn is_transient(why: &SerenityError) -> bool {
+    match why {
+        SerenityError::Http(http_error) => match http_error.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                !response.status_code.is_client_error() || response.status_code.as_u16() == 429
+            }
+            _ => true,
+        },
+        _ => true,
+    }
+}
@@ -0,0 +1,96 @@
+use log::{error, info};
+use serde::{de::DeserializeOwned, Serialize};
+use serenity::prelude::*;
+use std::{
+    env, fs,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use super::context::StoreContainer;
+
+const DEFAULT_DATA_DIR: &str = "data";
+
+/// A value that is persisted as a json file in the data directory,
+/// which can be set with the DATA_DIR environment variable.
+pub struct Store<T> {
+    path: PathBuf,
+    value: T,
+}
+
+impl<T> Store<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    /// Loads the store with the given name from disk, or creates an empty one if none exists yet
+    pub fn load(name: &str) -> Self {
+        let data_dir = env::var("DATA_DIR").unwrap_or_else(|_| DEFAULT_DATA_DIR.to_string());
+        let path = PathBuf::from(data_dir).join(format!("{}.json", name));
+
+        let value = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|why| {
+                panic!("Couldn't parse store file {}: {:?}", path.display(), why)
+            }),
+            Err(_) => {
+                info!("No store file found at {}, starting empty", path.display());
+                T::default()
+            }
+        };
+
+        Store { path, value }
+    }
+
+    /// Writes the current value to disk. Writes go to a temporary file first,
+    /// so a crash while saving can't leave a half written store behind.
+    pub fn save(&self) {
+        let result = (|| -> std::io::Result<()> {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            let tmp_path = self.path.with_extension("json.tmp");
+            fs::write(&tmp_path, serde_json::to_vec_pretty(&self.value)?)?;
+            fs::rename(&tmp_path, &self.path)
+        })();
+
+        if let Err(why) = result {
+            error!("Couldn't save store {}: {:?}", self.path.display(), why);
+        }
+    }
+}
+
+impl<T> Deref for Store<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Store<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Loads a store from disk and makes it available in the client data
+pub fn register_store<T>(data: &mut TypeMap, name: &str)
+where
+    T: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    data.insert::<StoreContainer<T>>(Arc::new(RwLock::new(Store::load(name))));
+}
+
+/// Retrieves a store that has been registered with `register_store`
+pub async fn get_store<T>(ctx: &Context) -> Arc<RwLock<Store<T>>>
+where
+    T: Send + Sync + 'static,
+{
+    ctx.data
+        .read()
+        .await
+        .get::<StoreContainer<T>>()
+        .expect("Couldn't get store from context.")
+        .clone()
+}
@@ -1,4 +1,12 @@
-use serenity::{client::Context, model::channel::Message};
+use chrono::Duration;
+use serenity::{
+    client::Context,
+    model::{
+        channel::{Message, ReactionType},
+        id::{ChannelId, GuildId},
+    },
+};
+use std::convert::TryFrom;
 
 use super::constants::ERROR_COLOR;
 
@@ -16,6 +24,23 @@ pub fn guild_icon_url(guild_id: &str, icon_id: &str, size: u16) -> String {
     }
 }
 
+pub fn uppercase_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Whether a channel belongs to the given guild. Commands that take a channel to post to
+/// check this, so servers can't make the bot post into channels of other servers.
+pub async fn is_guild_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> bool {
+    ctx.cache
+        .guild_channel_field(channel_id, |c| c.guild_id)
+        .await
+        == Some(guild_id)
+}
+
 pub async fn send_error_msg(ctx: &Context, msg: &Message, title: Option<&str>, error_msg: &str) {
     let _ = msg
         .channel_id
@@ -32,3 +57,91 @@ pub async fn send_error_msg(ctx: &Context, msg: &Message, title: Option<&str>, e
         })
         .await;
 }
+
+// Longer durations are almost certainly typos and would overflow date arithmetic eventually
+const MAX_DURATION_SECS: i64 = 5 * 365 * 86400;
+
+/// Parses durations like "30m", "2h" or "1d12h" into a chrono Duration.
+/// Supported units are s, m, h, d and w, the total can be at most five years.
+pub fn parse_duration(duration_str: &str) -> Option<Duration> {
+    let mut total_seconds: i64 = 0;
+    let mut number = String::new();
+
+    for c in duration_str.to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let value = number.parse::<i64>().ok()?;
+        number.clear();
+
+        let unit_seconds = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+
+        total_seconds = value
+            .checked_mul(unit_seconds)
+            .and_then(|seconds| total_seconds.checked_add(seconds))?;
+    }
+
+    // Don't accept trailing numbers without a unit, empty or overly long durations
+    if !number.is_empty() || total_seconds <= 0 || total_seconds > MAX_DURATION_SECS {
+        return None;
+    }
+
+    Some(Duration::seconds(total_seconds))
+}
+
+/// Formats a duration into a short human readable form, i.e. "1d 2h 30m"
+pub fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.num_seconds();
+    let parts = [
+        (total_seconds / 86400, "d"),
+        (total_seconds % 86400 / 3600, "h"),
+        (total_seconds % 3600 / 60, "m"),
+        (total_seconds % 60, "s"),
+    ];
+
+    let formatted: Vec<String> = parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    match formatted.is_empty() {
+        true => "0s".to_string(),
+        false => formatted.join(" "),
+    }
+}
+
+/// Parses an emoji argument into a reaction. Custom emojis are given as `<:name:id>`,
+/// anything else has to be a short non-ascii text to be considered a unicode emoji.
+pub fn parse_emoji(arg: &str) -> Option<ReactionType> {
+    if arg.starts_with('<') {
+        return ReactionType::try_from(arg).ok();
+    }
+
+    match !arg.is_ascii() && arg.chars().count() <= 8 && !arg.chars().any(char::is_alphabetic) {
+        true => Some(ReactionType::Unicode(arg.to_string())),
+        false => None,
+    }
+}
+
+/// Compares two reaction emojis. Custom emojis are compared by id only,
+/// as their name and animated flag aren't always known.
+pub fn reaction_matches(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        (ReactionType::Unicode(a), ReactionType::Unicode(b)) => {
+            // Some clients send emojis with or without the variation selector
+            a.trim_end_matches('\u{fe0f}') == b.trim_end_matches('\u{fe0f}')
+        }
+        _ => false,
+    }
+}
@@ -1,20 +1,39 @@
 mod commands;
 mod core;
 
+use crate::commands::moderation::{
+    antiraid, automod, autorole, cases::log_external_action, logging, rolemenu, starboard, welcome,
+};
+use crate::core::cache::TtlCache;
 use crate::core::context::*;
+use crate::core::scheduler::{self, register_executor, ActionKind, ScheduleData};
+use crate::core::storage::register_store;
 use crate::core::util::send_error_msg;
 use chrono::Utc;
 use log::{error, info};
 use serenity::{
     async_trait,
+    client::bridge::gateway::GatewayIntents,
     framework::standard::{macros::hook, CommandResult, DispatchError, Reason, StandardFramework},
     http::Http,
-    model::{channel::Message, event::ResumedEvent, gateway::Ready},
+    model::{
+        channel::{Channel, GuildChannel, Message, Reaction},
+        event::{MessageUpdateEvent, ResumedEvent},
+        gateway::Ready,
+        guild::{ActionMember, Member, Role},
+        id::{ChannelId, GuildId, MessageId, RoleId},
+        interactions::Interaction,
+        user::User,
+    },
     prelude::*,
 };
-use std::{collections::HashSet, env, sync::Arc};
+use std::{collections::HashSet, env, sync::Arc, time::Duration};
 use sysinfo::{System, SystemExt};
 
+const MAX_CACHED_MESSAGES: usize = 500;
+// Weather barely changes within this time, so repeated lookups don't need to use up the api quota
+const WEATHER_CACHE_TTL_SECS: u64 = 10 * 60;
+
 struct Handler;
 
 #[async_trait]
@@ -29,12 +48,141 @@ impl EventHandler for Handler {
             Some(Activity::listening("~help, mio help")),
             OnlineStatus::Online,
         )
-        .await
+        .await;
+
+        // Messages are cached so that their content can be logged after edits and deletions
+        ctx.cache.set_max_messages(MAX_CACHED_MESSAGES).await;
+
+        scheduler::start(ctx);
+    }
+
+    async fn message(&self, ctx: Context, msg: Message) {
+        automod::check_message(&ctx, &msg).await;
     }
 
     async fn resume(&self, _: Context, _: ResumedEvent) {
         info!("Resumed");
     }
+
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        autorole::handle_gate_reaction(&ctx, &reaction).await;
+        rolemenu::handle_reaction_add(&ctx, &reaction).await;
+        starboard::handle_reaction(&ctx, &reaction).await;
+        commands::web::handle_flag_reaction(&ctx, &reaction).await;
+    }
+
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        rolemenu::handle_reaction_remove(&ctx, &reaction).await;
+        starboard::handle_reaction(&ctx, &reaction).await;
+    }
+
+    async fn reaction_remove_all(&self, ctx: Context, _: ChannelId, message_id: MessageId) {
+        starboard::handle_reaction_remove_all(&ctx, message_id).await;
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        rolemenu::handle_interaction(&ctx, &interaction).await;
+    }
+
+    async fn guild_ban_addition(&self, ctx: Context, guild_id: GuildId, banned_user: User) {
+        log_external_action(&ctx, guild_id, banned_user.id, ActionMember::BanAdd).await;
+    }
+
+    async fn guild_ban_removal(&self, ctx: Context, guild_id: GuildId, unbanned_user: User) {
+        log_external_action(&ctx, guild_id, unbanned_user.id, ActionMember::BanRemove).await;
+    }
+
+    async fn guild_member_addition(&self, ctx: Context, guild_id: GuildId, new_member: Member) {
+        logging::log_member_join(&ctx, guild_id, &new_member).await;
+        antiraid::check_member_join(&ctx, guild_id, &new_member).await;
+        autorole::handle_member_join(&ctx, guild_id, &new_member).await;
+        welcome::send_welcome(&ctx, guild_id, &new_member.user).await;
+    }
+
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        member: Option<Member>,
+    ) {
+        logging::log_member_leave(&ctx, guild_id, &user, member).await;
+        welcome::send_goodbye(&ctx, guild_id, &user).await;
+        log_external_action(&ctx, guild_id, user.id, ActionMember::Kick).await;
+    }
+
+    async fn guild_member_update(&self, ctx: Context, old: Option<Member>, new: Member) {
+        logging::log_member_update(&ctx, old, &new).await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: Context,
+        old: Option<Message>,
+        _: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        logging::log_message_edit(&ctx, old, &event).await;
+        automod::check_message_edit(&ctx, &event).await;
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        logging::log_message_delete(&ctx, guild_id, channel_id, message_id).await;
+        rolemenu::handle_message_delete(&ctx, &[message_id]).await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        message_ids: Vec<MessageId>,
+        guild_id: Option<GuildId>,
+    ) {
+        logging::log_message_delete_bulk(&ctx, guild_id, channel_id, &message_ids).await;
+        rolemenu::handle_message_delete(&ctx, &message_ids).await;
+    }
+
+    async fn guild_role_create(&self, ctx: Context, guild_id: GuildId, role: Role) {
+        logging::log_role_create(&ctx, guild_id, &role).await;
+    }
+
+    async fn guild_role_delete(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        role_id: RoleId,
+        role: Option<Role>,
+    ) {
+        logging::log_role_delete(&ctx, guild_id, role_id, role).await;
+    }
+
+    async fn guild_role_update(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        old: Option<Role>,
+        new: Role,
+    ) {
+        logging::log_role_update(&ctx, guild_id, old, &new).await;
+    }
+
+    async fn channel_create(&self, ctx: Context, channel: &GuildChannel) {
+        logging::log_channel_create(&ctx, channel).await;
+    }
+
+    async fn channel_delete(&self, ctx: Context, channel: &GuildChannel) {
+        logging::log_channel_delete(&ctx, channel).await;
+    }
+
+    async fn channel_update(&self, ctx: Context, old: Option<Channel>, new: Channel) {
+        logging::log_channel_update(&ctx, old, new).await;
+    }
 }
 
 #[tokio::main]
@@ -76,12 +224,14 @@ async fn main() {
         .group(&commands::web::WEB_GROUP)
         .group(&commands::fun::FUN_GROUP)
         .group(&commands::system::SYSTEM_GROUP)
+        .group(&commands::utility::UTILITY_GROUP)
         .group(&commands::moderation::MODERATION_GROUP)
         .group(&commands::nsfw::NSFW_GROUP)
         .help(&commands::help::HELP);
 
     let mut client = Client::builder(&token)
         .event_handler(Handler)
+        .intents(GatewayIntents::non_privileged() | GatewayIntents::GUILD_MEMBERS)
         .framework(framework)
         .await
         .expect("Err creating client");
@@ -93,6 +243,46 @@ async fn main() {
         data.insert::<SysInfoContainer>(System::new_all());
         data.insert::<AppInfoContainer>(app_info);
         data.insert::<BotUserContainer>(bot_user);
+        data.insert::<HttpClientContainer>(reqwest::Client::new());
+        data.insert::<WeatherCacheContainer>(Arc::new(Mutex::new(TtlCache::new(
+            Duration::from_secs(WEATHER_CACHE_TTL_SECS),
+        ))));
+
+        register_store::<commands::moderation::WarningData>(&mut data, "warnings");
+        register_store::<commands::moderation::CaseData>(&mut data, "cases");
+        register_store::<commands::moderation::AutomodData>(&mut data, "automod");
+        register_store::<commands::moderation::AntiraidData>(&mut data, "antiraid");
+        register_store::<commands::moderation::LoggingData>(&mut data, "logging");
+        register_store::<commands::moderation::WelcomeData>(&mut data, "welcome");
+        register_store::<commands::moderation::AutoroleData>(&mut data, "autorole");
+        register_store::<commands::moderation::RoleMenuData>(&mut data, "rolemenus");
+        register_store::<commands::moderation::StarboardData>(&mut data, "starboard");
+        register_store::<commands::utility::ReminderData>(&mut data, "reminders");
+        register_store::<commands::web::WeatherData>(&mut data, "weather");
+        register_store::<commands::web::TranslateData>(&mut data, "translate");
+        register_store::<ScheduleData>(&mut data, "schedule");
+
+        register_executor(
+            &mut data,
+            ActionKind::Unban,
+            commands::moderation::UnbanExecutor,
+        );
+        register_executor(
+            &mut data,
+            ActionKind::RemoveRole,
+            commands::moderation::RemoveRoleExecutor,
+        );
+        register_executor(&mut data, ActionKind::AddRoles, autorole::AddRolesExecutor);
+        register_executor(
+            &mut data,
+            ActionKind::Reminder,
+            commands::utility::ReminderExecutor,
+        );
+        register_executor(
+            &mut data,
+            ActionKind::WeatherForecast,
+            commands::web::ForecastExecutor,
+        );
     }
 
     if let Err(why) = client.start().await {